@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::File;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::json;
+
+use super::errors::SNNError;
+use super::fault::{Fault, FaultComponent, FaultKind};
+use super::neural_network::NeuralNetwork;
+use super::rng::Rng;
+
+/*
+Insieme dei guasti da cui estrarre una campagna: per ogni neurone dei layer selezionati, ogni componente esposto dal suo modello,
+bit e tipo di guasto (e, per i bit-flip, ogni passo in times). I guasti sui pesi riguardano ogni sinapsi del neurone.
+*/
+#[derive(Clone, Debug)]
+pub struct FaultSpace {
+    // numero di sinapsi di ogni neurone, per layer (vedi NeuralNetwork::shape)
+    pub shape: Vec<Vec<usize>>,
+    // componenti esposti da ogni neurone, per layer (vedi NeuralNetwork::fault_components): quelli di components che il
+    // modello di un neurone non espone vengono saltati
+    pub exposed: Vec<Vec<Vec<FaultComponent>>>,
+    pub layers: Vec<usize>,
+    pub components: Vec<FaultComponent>,
+    pub kinds: Vec<FaultKind>,
+    pub bits: Vec<u8>,
+    pub times: Vec<usize>,
+}
+
+impl FaultSpace {
+    pub fn new(nn: &mut NeuralNetwork) -> Self {
+        // tutti i layer, tipi e bit, con i bit-flip al primo passo, e tutti i componenti esposti da almeno un neurone; i segnali
+        // del datapath si aggiungono a components con hardware::datapath_components
+        let shape = nn.shape();
+        let exposed = nn.fault_components();
+        let components: BTreeSet<FaultComponent> = exposed
+            .iter()
+            .flatten()
+            .flatten()
+            .copied()
+            .filter(|c| !matches!(c, FaultComponent::Datapath { .. }))
+            .collect();
+        Self {
+            layers: (0..shape.len()).collect(),
+            shape,
+            exposed,
+            components: components.into_iter().collect(),
+            kinds: vec![FaultKind::StuckAt0, FaultKind::StuckAt1, FaultKind::BitFlip],
+            bits: (0..64).collect(),
+            times: vec![1],
+        }
+    }
+
+    fn sites(&self, layer: usize, neuron: usize, component: FaultComponent) -> Vec<Option<usize>> {
+        // posizioni del componente nel neurone: una per sinapsi per i pesi, altrimenti il neurone stesso, nessuna se il
+        // modello non espone il componente
+        if !self.exposed[layer][neuron].contains(&component) {
+            return vec![];
+        }
+        match component {
+            FaultComponent::Weight => (0..self.shape[layer][neuron]).map(Some).collect(),
+            _ => vec![None],
+        }
+    }
+
+    fn times_of(&self, kind: FaultKind) -> &[usize] {
+        // i guasti permanenti non dipendono dal passo
+        if kind.is_permanent() { &[0] } else { &self.times }
+    }
+
+    pub fn exhaustive(&self) -> Vec<Fault> {
+        let mut faults = vec![];
+        for &layer in &self.layers {
+            for neuron in 0..self.shape[layer].len() {
+                for &component in &self.components {
+                    for synapse in self.sites(layer, neuron, component) {
+                        for &bit in &self.bits {
+                            for &kind in &self.kinds {
+                                for &time in self.times_of(kind) {
+                                    faults.push(Fault::new(component, layer, neuron, synapse, bit, kind, time));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        faults
+    }
+
+    pub fn random(&self, n: usize, seed: u64) -> Vec<Fault> {
+        // n guasti estratti in modo uniforme su ogni dimensione (layer, neurone, componente, sinapsi, bit, tipo, passo)
+        let mut rng = Rng::new(seed);
+        let mut faults = Vec::with_capacity(n);
+        let attempts = n.saturating_mul(100);
+        for _ in 0..attempts {
+            if faults.len() == n || self.layers.is_empty() || self.components.is_empty() || self.bits.is_empty() || self.kinds.is_empty() {
+                break;
+            }
+            let layer = self.layers[rng.below(self.layers.len())];
+            if self.shape[layer].is_empty() {
+                continue;
+            }
+            let neuron = rng.below(self.shape[layer].len());
+            let component = self.components[rng.below(self.components.len())];
+            let sites = self.sites(layer, neuron, component);
+            // un neurone senza sinapsi non ha pesi e un modello può non esporre il componente, si estrae un altro guasto
+            if sites.is_empty() {
+                continue;
+            }
+            let synapse = sites[rng.below(sites.len())];
+            let bit = self.bits[rng.below(self.bits.len())];
+            let kind = self.kinds[rng.below(self.kinds.len())];
+            let times = self.times_of(kind);
+            if times.is_empty() {
+                continue;
+            }
+            let time = times[rng.below(times.len())];
+            faults.push(Fault::new(component, layer, neuron, synapse, bit, kind, time));
+        }
+        faults
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    // stesso numero di spike della simulazione senza guasti
+    Masked,
+    // la simulazione termina ma l'output è diverso (silent data corruption)
+    Sdc,
+    // errore o panic durante la costruzione della rete o la simulazione con il guasto
+    Crash,
+    // la simulazione non termina entro il timeout
+    Hang,
+}
+
+const OUTCOMES: [Outcome; 4] = [Outcome::Masked, Outcome::Sdc, Outcome::Crash, Outcome::Hang];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FaultResult {
+    pub fault: Fault,
+    pub outcome: Outcome,
+    // numero di spike dell'ultimo layer, None per crash e hang
+    pub counts: Option<Vec<i32>>,
+}
+
+/*
+Esegue una campagna di guasti: una simulazione senza guasti (golden run) seguita da una simulazione per guasto, ognuna su una rete
+nuova costruita da build. Le simulazioni vengono distribuite su workers thread; ogni simulazione gira in un thread separato in
+modo da poterla abbandonare allo scadere del timeout (il thread non può essere interrotto e continua in background fino al
+termine del programma).
+*/
+pub struct CampaignRunner<F> {
+    build: Arc<F>,
+    workers: usize,
+    // None: 10 volte la durata della golden run, almeno un secondo
+    timeout: Option<Duration>,
+}
+
+impl<F> CampaignRunner<F>
+where
+    F: Fn() -> Result<NeuralNetwork, SNNError> + Send + Sync + 'static,
+{
+    pub fn new(build: F) -> Self {
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        Self { build: Arc::new(build), workers, timeout: None }
+    }
+
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn simulate(&self, fault: Option<Fault>, timeout: Option<Duration>) -> Result<Vec<i32>, Outcome> {
+        // simula una rete nuova con il guasto indicato, nel thread dedicato
+        let (tx, rx) = mpsc::channel();
+        let build = Arc::clone(&self.build);
+        thread::spawn(move || {
+            let result = build().and_then(|mut nn| {
+                if let Some(fault) = fault {
+                    nn.add_fault(fault)?;
+                }
+                nn.simulate()
+            });
+            // il ricevitore potrebbe aver già rinunciato per il timeout
+            let _ = tx.send(result);
+        });
+        let received = match timeout {
+            None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+            Some(timeout) => rx.recv_timeout(timeout),
+        };
+        match received {
+            Ok(Ok(counts)) => Ok(counts),
+            // errore della simulazione, o panic del thread che chiude il canale senza inviare nulla
+            Ok(Err(_)) | Err(mpsc::RecvTimeoutError::Disconnected) => Err(Outcome::Crash),
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(Outcome::Hang),
+        }
+    }
+
+    pub fn run(&self, faults: &[Fault]) -> Result<CampaignReport, SNNError> {
+        // golden run (senza timeout, è il riferimento), poi una simulazione per guasto; i risultati sono nello stesso ordine di
+        // faults. Errore se un guasto non si può iniettare nella rete, che altrimenti verrebbe contato come crash
+        {
+            let mut nn = (self.build)()?;
+            for fault in faults {
+                nn.add_fault(*fault)?;
+            }
+        }
+
+        let start = Instant::now();
+        let golden = match self.simulate(None, None) {
+            Ok(counts) => counts,
+            Err(outcome) => return Err(SNNError::EngineError(format!("Golden run failed ({outcome:?})"))),
+        };
+        let timeout = self.timeout.unwrap_or_else(|| (start.elapsed() * 10).max(Duration::from_secs(1)));
+
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(vec![None; faults.len()]);
+        thread::scope(|scope| {
+            for _ in 0..self.workers.min(faults.len()) {
+                scope.spawn(|| loop {
+                    let f = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(fault) = faults.get(f) else { break };
+                    let result = match self.simulate(Some(*fault), Some(timeout)) {
+                        Ok(counts) if counts == golden => FaultResult { fault: *fault, outcome: Outcome::Masked, counts: Some(counts) },
+                        Ok(counts) => FaultResult { fault: *fault, outcome: Outcome::Sdc, counts: Some(counts) },
+                        Err(outcome) => FaultResult { fault: *fault, outcome, counts: None },
+                    };
+                    results.lock().unwrap()[f] = Some(result);
+                });
+            }
+        });
+        let results = results.into_inner().unwrap().into_iter().flatten().collect();
+        Ok(CampaignReport { golden, results })
+    }
+}
+
+/*
+Risultato di una campagna: output della golden run ed esito di ogni guasto, con i conteggi aggregati per componente, layer e
+posizione del bit.
+*/
+#[derive(Clone, Debug)]
+pub struct CampaignReport {
+    pub golden: Vec<i32>,
+    pub results: Vec<FaultResult>,
+}
+
+impl CampaignReport {
+    pub fn totals(&self) -> [usize; 4] {
+        // numero di simulazioni per esito, nell'ordine masked, sdc, crash, hang
+        tally(self.results.iter())
+    }
+
+    pub fn by_component(&self) -> BTreeMap<FaultComponent, [usize; 4]> {
+        self.group_by(|r| r.fault.component)
+    }
+
+    pub fn by_layer(&self) -> BTreeMap<usize, [usize; 4]> {
+        self.group_by(|r| r.fault.layer)
+    }
+
+    pub fn by_bit(&self) -> BTreeMap<u8, [usize; 4]> {
+        self.group_by(|r| r.fault.bit)
+    }
+
+    fn group_by<K: Ord>(&self, key: impl Fn(&FaultResult) -> K) -> BTreeMap<K, [usize; 4]> {
+        let mut groups: BTreeMap<K, Vec<&FaultResult>> = BTreeMap::new();
+        for result in &self.results {
+            groups.entry(key(result)).or_default().push(result);
+        }
+        groups.into_iter().map(|(k, results)| (k, tally(results.into_iter()))).collect()
+    }
+
+    fn rows(&self) -> Vec<(String, String, [usize; 4])> {
+        // righe aggregate (gruppo, chiave, conteggi) comuni al CSV e al testo
+        let mut rows = vec![("all".to_string(), "all".to_string(), self.totals())];
+        rows.extend(self.by_component().into_iter().map(|(k, c)| ("component".to_string(), component_name(k), c)));
+        rows.extend(self.by_layer().into_iter().map(|(k, c)| ("layer".to_string(), k.to_string(), c)));
+        rows.extend(self.by_bit().into_iter().map(|(k, c)| ("bit".to_string(), k.to_string(), c)));
+        rows
+    }
+
+    pub fn write_csv(&self, path: &str) -> Result<(), SNNError> {
+        // una riga per gruppo: totale, componenti, layer e bit
+        let mut csv = String::from("group,key,runs,masked,sdc,crash,hang\n");
+        for (group, key, c) in self.rows() {
+            csv.push_str(&format!("{group},{key},{},{},{},{},{}\n", c.iter().sum::<usize>(), c[0], c[1], c[2], c[3]));
+        }
+        match std::fs::write(path, csv) {
+            Err(e) => Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let groups = |rows: Vec<(String, [usize; 4])>| -> Vec<serde_json::Value> {
+            rows.into_iter().map(|(key, c)| json!({ "key": key, "runs": c.iter().sum::<usize>(), "outcomes": outcomes_json(c) })).collect()
+        };
+        json!({
+            "golden": self.golden,
+            "runs": self.results.len(),
+            "outcomes": outcomes_json(self.totals()),
+            "by_component": groups(self.by_component().into_iter().map(|(k, c)| (component_name(k), c)).collect()),
+            "by_layer": groups(self.by_layer().into_iter().map(|(k, c)| (k.to_string(), c)).collect()),
+            "by_bit": groups(self.by_bit().into_iter().map(|(k, c)| (k.to_string(), c)).collect()),
+            "results": self.results,
+        })
+    }
+
+    pub fn write_json(&self, path: &str) -> Result<(), SNNError> {
+        let file = match File::create(path) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot create file {path}."))),
+            Ok(f) => f,
+        };
+        match serde_json::to_writer_pretty(file, &self.to_json()) {
+            Err(e) => Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub fn save(&self, csv_path: &str, json_path: &str) -> Result<(), SNNError> {
+        // scrive il report aggregato in CSV e quello completo in JSON
+        self.write_csv(csv_path)?;
+        self.write_json(json_path)
+    }
+}
+
+fn tally<'a>(results: impl Iterator<Item = &'a FaultResult>) -> [usize; 4] {
+    let mut counts = [0; 4];
+    for result in results {
+        counts[OUTCOMES.iter().position(|o| *o == result.outcome).unwrap()] += 1;
+    }
+    counts
+}
+
+fn outcomes_json(counts: [usize; 4]) -> serde_json::Value {
+    json!({ "masked": counts[0], "sdc": counts[1], "crash": counts[2], "hang": counts[3] })
+}
+
+fn component_name(component: FaultComponent) -> String {
+    // stesso nome usato nel file JSON, datapath.unità.porta per i segnali del datapath
+    let name = |value: serde_json::Value| value.as_str().map(str::to_string).unwrap_or_default();
+    match component {
+        FaultComponent::Datapath { unit, port } => {
+            format!("datapath.{}.{}", name(json!(unit)), name(json!(port)))
+        }
+        component => name(json!(component)),
+    }
+}
+
+impl fmt::Display for CampaignReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self.rows();
+        // la colonna delle chiavi si allarga per i nomi dei segnali del datapath
+        let width = rows.iter().map(|(_, key, _)| key.len()).max().unwrap_or(0).max(12);
+        writeln!(f, "golden output: {:?}", self.golden)?;
+        writeln!(f, "{:<10} {:<width$} {:>8} {:>8} {:>8} {:>8} {:>8}", "group", "key", "runs", "masked", "sdc", "crash", "hang")?;
+        for (group, key, c) in rows {
+            writeln!(f, "{:<10} {:<width$} {:>8} {:>8} {:>8} {:>8} {:>8}", group, key, c.iter().sum::<usize>(), c[0], c[1], c[2], c[3])?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::VecDeque;
+
+use super::{input_layer::InputLayer, neural_layer::NeuralLayer, neuron::Neuron, output::{OutputMonitor, OutputRecord}, spike::Spike, errors::SNNError};
+use super::event_engine::{last_ticks, spike_of};
+use super::sparse::Csr;
+use super::synapse::Projection;
+use super::raster::{RasterTarget, SpikeRaster};
+
+// spike in arrivo a ogni neurone di ogni layer come (sinapsi, spike)
+pub type Inbox = Vec<Vec<Vec<(usize, i8)>>>;
+
+/*
+Motore a passo fisso in un solo thread. A ogni passo ts i layer vengono aggiornati in ordine e le spike emesse vengono
+consegnate scorrendo solo le righe della connettività in uscita (vedi Fanout) delle sorgenti che hanno emesso, con la stessa
+temporizzazione del motore multi-thread:
+- una sinapsi con peso negativo viene saltata al primo passo, quindi legge sempre la spike emessa un passo prima (lag 1);
+- le altre sinapsi leggono la spike emessa nello stesso passo (lag 0), per cui un neurone viene aggiornato dopo i neuroni
+  dello stesso layer da cui riceve sinapsi intra positive;
+- un neurone termina al passo in cui una sorgente non ha più spike da inviare (vedi event_engine::last_ticks), emettendo un
+  ultimo 0 come farebbe il thread alla chiusura del canale.
+Un neurone con sole sinapsi passive riceve solo le spike non nulle, gli altri consegnano una spike a ogni sinapsi a ogni passo.
+*/
+pub fn run(input_layer: &InputLayer, layers: &mut [NeuralLayer], mut output_monitor: OutputMonitor, rasters: &mut [SpikeRaster]) -> Result<OutputRecord, SNNError> {
+    let orders = layers.iter().map(firing_order).collect::<Result<Vec<_>, _>>()?;
+    let last_ticks = last_ticks(input_layer, layers, &orders)?;
+    let horizon = last_ticks.iter().flatten().copied().max().unwrap_or(0);
+    let fanout = Fanout::new(input_layer, layers);
+    let passive: Vec<Vec<bool>> = layers.iter().map(|l| l.neurons.iter().map(|n| n.synapses.iter().all(|s| s.is_passive())).collect()).collect();
+
+    // spike in arrivo a ogni neurone come (sinapsi, spike), nei passi pari (0) e dispari (1)
+    let mut inbox: [Inbox; 2] = [0, 1].map(|_| layers.iter().map(|l| vec![vec![]; l.neurons.len()]).collect());
+    let last_layer = layers.len() - 1;
+    let monitored: Vec<bool> = (0..layers.len()).map(|l| rasters.iter().any(|r| r.target() == RasterTarget::Layer(l))).collect();
+    let mut fired = vec![];
+
+    for ts in 1..=horizon {
+        for (i, input) in input_layer.inputs.iter().enumerate() {
+            let spike = input.spikes().get(ts - 1).copied().unwrap_or(0);
+            if spike != 0 {
+                for (tl, tj, k, lag) in fanout.input_targets(i) {
+                    if ts + lag <= last_ticks[tl][tj] {
+                        inbox[(ts + lag) % 2][tl][tj].push((k, spike));
+                    }
+                }
+            }
+        }
+
+        for (l, layer) in layers.iter_mut().enumerate() {
+            for &j in &orders[l] {
+                if ts > last_ticks[l][j] {
+                    continue;
+                }
+                let mut events = std::mem::take(&mut inbox[ts % 2][l][j]);
+                let out_spike = if ts == last_ticks[l][j] {
+                    finish(&mut layer.neurons[j], l, j, ts, &mut events, input_layer, &last_ticks);
+                    0
+                } else {
+                    update(&mut layer.neurons[j], ts, &mut events, passive[l][j])?
+                };
+                // il vettore svuotato viene riusato nei passi successivi
+                events.clear();
+                inbox[ts % 2][l][j] = events;
+                if out_spike == 0 {
+                    continue;
+                }
+                for (tl, tj, k, lag) in fanout.targets(l, j) {
+                    if ts + lag <= last_ticks[tl][tj] {
+                        inbox[(ts + lag) % 2][tl][tj].push((k, out_spike));
+                    }
+                }
+                if l == last_layer {
+                    output_monitor.record(Spike::new(out_spike, Some(j as i32)), ts)?;
+                }
+                if monitored[l] {
+                    fired.push((l, j));
+                }
+            }
+        }
+
+        // i neuroni vengono aggiornati in ordine topologico, il raster li registra in ordine di indice
+        fired.sort();
+        for (l, j) in fired.drain(..) {
+            for raster in rasters.iter_mut().filter(|r| r.target() == RasterTarget::Layer(l)) {
+                raster.record(ts, j);
+            }
+        }
+    }
+
+    Ok(output_monitor.into_record())
+}
+
+pub fn update(neuron: &mut Neuron, ts: usize, events: &mut [(usize, i8)], passive: bool) -> Result<i8, SNNError> {
+    /*
+     * Aggiorna il neurone al passo ts, prima del suo ultimo passo, con le spike ricevute come (sinapsi, spike) e restituisce la
+     * spike emessa. Nel controllo del lag il peso è quello letto dopo i guasti del passo, che al primo passo (l'unico in cui
+     * conta) dà lo stesso lag di Neuron::lag.
+     */
+    events.sort_unstable_by_key(|(k, _)| *k);
+    let weighted_inputs = if passive {
+        // le sinapsi passive senza spike non contribuiscono, basta consegnare gli eventi
+        neuron.collect_events(events)
+    } else {
+        // le sinapsi senza eventi ricevono 0
+        neuron.collect_inputs(|k, synapse| {
+            let lag = usize::from(synapse.get_weight() < 0.0);
+            Ok(if ts <= lag { None } else { Some(spike_of(events, k)) })
+        })?
+    };
+    let spike = neuron.step(&weighted_inputs);
+    neuron.sample(ts);
+    Ok(spike)
+}
+
+pub fn finish(neuron: &mut Neuron, l: usize, j: usize, ts: usize, events: &mut [(usize, i8)], input_layer: &InputLayer, last_ticks: &[Vec<usize>]) {
+    // ultimo passo del neurone j del layer l: come nel motore multi-thread le sinapsi ricevono le spike fino alla prima sorgente
+    // che ha terminato, in modo da lasciarle nello stesso stato, e il neurone emette 0
+    debug_assert_eq!(ts, last_ticks[l][j]);
+    events.sort_unstable_by_key(|(k, _)| *k);
+    let _ = neuron.collect_inputs(|k, synapse| {
+        let lag = usize::from(synapse.get_weight() < 0.0);
+        if ts <= lag {
+            return Ok(None);
+        }
+        let i = synapse.get_source();
+        let produced = match synapse.get_projection() {
+            Projection::Input if l == 0 => input_layer.inputs[i].spikes().len(),
+            Projection::Input => last_ticks[l - 1][i],
+            Projection::Intra => last_ticks[l][i],
+        };
+        if ts - lag > produced {
+            return Err(SNNError::EmptyChannelError("Comunication ended".to_string()));
+        }
+        Ok(Some(spike_of(events, k)))
+    });
+}
+
+/*
+Connettività in uscita usata dai motori a un solo thread e da quello parallelo, costruita una volta per simulazione dalle sinapsi
+dei neuroni. È memorizzata in formato CSR: una riga per ogni input e per ogni neurone, con i neuroni raggiunti come colonne e,
+come valori, l'indice della sinapsi nel neurone raggiunto e il suo lag. Consegnare le spike di un passo costa quindi quanto le
+sinapsi delle sorgenti che le hanno emesse.
+*/
+pub struct Fanout {
+    // righe: input, colonne: neuroni del primo layer
+    inputs: Csr<(usize, usize)>,
+    // righe: neuroni del layer l, colonne: neuroni del layer l (sinapsi intra) seguiti da quelli del layer l + 1
+    layers: Vec<Csr<(usize, usize)>>,
+}
+
+impl Fanout {
+    pub fn new(input_layer: &InputLayer, layers: &[NeuralLayer]) -> Self {
+        let sizes: Vec<usize> = layers.iter().map(|l| l.neurons.len()).collect();
+        let mut inputs = vec![];
+        let mut entries: Vec<Vec<_>> = layers.iter().map(|_| vec![]).collect();
+        for (l, layer) in layers.iter().enumerate() {
+            for (j, neuron) in layer.neurons.iter().enumerate() {
+                for (k, synapse) in neuron.synapses.iter().enumerate() {
+                    let i = synapse.get_source();
+                    let value = (k, neuron.lag(k));
+                    match synapse.get_projection() {
+                        Projection::Input if l == 0 => inputs.push((i, j, value)),
+                        Projection::Input => entries[l - 1].push((i, sizes[l - 1] + j, value)),
+                        Projection::Intra => entries[l].push((i, j, value)),
+                    }
+                }
+            }
+        }
+        let next = |l: usize| sizes.get(l + 1).copied().unwrap_or(0);
+        Self {
+            inputs: Csr::from_entries(input_layer.inputs.len(), sizes.first().copied().unwrap_or(0), inputs),
+            layers: entries.into_iter().enumerate().map(|(l, e)| Csr::from_entries(sizes[l], sizes[l] + next(l), e)).collect(),
+        }
+    }
+
+    pub fn input_targets(&self, i: usize) -> impl Iterator<Item = (usize, usize, usize, usize)> + '_ {
+        // sinapsi raggiunte dall'input i come (layer, neurone, sinapsi, lag)
+        self.inputs.row(i).map(|(j, (k, lag))| (0, j, k, lag))
+    }
+
+    pub fn targets(&self, l: usize, i: usize) -> impl Iterator<Item = (usize, usize, usize, usize)> + '_ {
+        // sinapsi raggiunte dal neurone i del layer l come (layer, neurone, sinapsi, lag)
+        let size = self.layers[l].rows();
+        self.layers[l].row(i).map(move |(j, (k, lag))| if j < size { (l, j, k, lag) } else { (l + 1, j - size, k, lag) })
+    }
+}
+
+pub fn firing_order(layer: &NeuralLayer) -> Result<Vec<usize>, SNNError> {
+    // ordine topologico dei neuroni del layer rispetto alle sinapsi intra con lag 0, errore se formano un ciclo (con il
+    // motore multi-thread i neuroni del ciclo resterebbero in attesa l'uno dell'altro)
+    let n = layer.neurons.len();
+    let mut successors = vec![vec![]; n];
+    let mut in_degree = vec![0; n];
+    for (j, neuron) in layer.neurons.iter().enumerate() {
+        for (k, synapse) in neuron.synapses.iter().enumerate() {
+            if synapse.get_projection() == Projection::Intra && neuron.lag(k) == 0 {
+                successors[synapse.get_source()].push(j);
+                in_degree[j] += 1;
+            }
+        }
+    }
+
+    let mut ready: VecDeque<usize> = (0..n).filter(|j| in_degree[*j] == 0).collect();
+    let mut order = Vec::with_capacity(n);
+    while let Some(i) = ready.pop_front() {
+        order.push(i);
+        for &j in &successors[i] {
+            in_degree[j] -= 1;
+            if in_degree[j] == 0 {
+                ready.push_back(j);
+            }
+        }
+    }
+    if order.len() < n {
+        return Err(SNNError::EngineError("Positive intra-layer synapses form a cycle, the network cannot be stepped".to_string()));
+    }
+    Ok(order)
+}
@@ -0,0 +1,292 @@
+use std::fmt;
+use std::fs::File;
+
+use libm::round;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::errors::SNNError;
+use super::fixed::QuantizationError;
+use super::sparse::{Csr, MatrixSpec};
+
+/*
+Potatura dei pesi: per soglia sul modulo oppure di una frazione dei pesi non nulli di ogni matrice, a partire da quelli di
+modulo minore.
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Pruning {
+    Magnitude { threshold: f64 },
+    Percentage { fraction: f64 },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleGranularity {
+    // una scala per layer, comune a input_weights[l] e intra_layer_weights[l]
+    #[default]
+    Layer,
+    // una scala per matrice
+    Matrix,
+}
+
+/*
+Trasformazione dei pesi di una rete dopo l'addestramento: prima la potatura, poi la quantizzazione uniforme simmetrica su bits
+bit (valori interi in [-(2^(bits-1) - 1), 2^(bits-1) - 1] moltiplicati per la scala, pari al massimo modulo dei pesi diviso
+2^(bits-1) - 1). Entrambe sono opzionali.
+*/
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct CompressionConfig {
+    pub bits: Option<u32>,
+    #[serde(default)]
+    pub scale: ScaleGranularity,
+    pub pruning: Option<Pruning>,
+}
+
+impl CompressionConfig {
+    fn check(&self) -> Result<(), SNNError> {
+        if let Some(bits) = self.bits {
+            if !(2..=53).contains(&bits) {
+                return Err(SNNError::BadFormatError(format!("Cannot quantize weights on {bits} bits, use 2 to 53")));
+            }
+        }
+        match self.pruning {
+            Some(Pruning::Percentage { fraction }) if !(0.0..=1.0).contains(&fraction) => {
+                Err(SNNError::BadFormatError(format!("The pruning fraction must be in [0, 1], got {fraction}")))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/*
+Effetto della trasformazione su una matrice dei pesi. La sparsità è calcolata sulla dimensione piena della matrice, l'errore
+di quantizzazione sui pesi sopravvissuti alla potatura.
+*/
+#[derive(Clone, Debug)]
+pub struct MatrixSummary {
+    pub name: String,
+    pub layer: usize,
+    pub entries: usize,
+    pub nonzero_before: usize,
+    pub nonzero_after: usize,
+    pub pruned: usize,
+    // None se i pesi non sono quantizzati o sono tutti nulli
+    pub scale: Option<f64>,
+    pub error: QuantizationError,
+}
+
+impl MatrixSummary {
+    pub fn sparsity_before(&self) -> f64 {
+        sparsity(self.nonzero_before, self.entries)
+    }
+
+    pub fn sparsity_after(&self) -> f64 {
+        sparsity(self.nonzero_after, self.entries)
+    }
+}
+
+fn sparsity(nonzero: usize, entries: usize) -> f64 {
+    match entries {
+        0 => 0.0,
+        n => 1.0 - nonzero as f64 / n as f64,
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CompressionReport {
+    pub matrices: Vec<MatrixSummary>,
+}
+
+impl CompressionReport {
+    pub fn to_json(&self) -> serde_json::Value {
+        let matrices: Vec<serde_json::Value> = self
+            .matrices
+            .iter()
+            .map(|m| {
+                json!({
+                    "matrix": m.name,
+                    "layer": m.layer,
+                    "entries": m.entries,
+                    "nonzero_before": m.nonzero_before,
+                    "nonzero_after": m.nonzero_after,
+                    "sparsity_before": m.sparsity_before(),
+                    "sparsity_after": m.sparsity_after(),
+                    "pruned": m.pruned,
+                    "scale": m.scale,
+                    "max_error": m.error.max_abs,
+                    "mean_error": m.error.mean_abs,
+                    "rmse": m.error.rmse,
+                    "quantized_to_zero": m.error.zeroed,
+                })
+            })
+            .collect();
+        json!({ "matrices": matrices })
+    }
+
+    pub fn write_json(&self, path: &str) -> Result<(), SNNError> {
+        let file = match File::create(path) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot create file {path}."))),
+            Ok(f) => f,
+        };
+        match serde_json::to_writer_pretty(file, &self.to_json()) {
+            Err(e) => Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+            Ok(_) => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for CompressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>5} {:>10} {:>10} {:>10} {:>8} {:>12} {:>12} {:>12}",
+            "matrix", "layer", "entries", "sparsity", "->", "pruned", "scale", "max error", "rmse"
+        )?;
+        for m in &self.matrices {
+            let scale = m.scale.map_or("-".to_string(), |s| format!("{s:.4e}"));
+            writeln!(
+                f,
+                "{:<24} {:>5} {:>10} {:>10.4} {:>10.4} {:>8} {:>12} {:>12.4e} {:>12.4e}",
+                m.name, m.layer, m.entries, m.sparsity_before(), m.sparsity_after(), m.pruned, scale, m.error.max_abs, m.error.rmse
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn prune(weights: &mut Csr<f64>, pruning: Pruning) -> usize {
+    // azzera i pesi scelti dalla potatura e ne restituisce il numero
+    let threshold = match pruning {
+        Pruning::Magnitude { threshold } => threshold,
+        Pruning::Percentage { fraction } => {
+            let mut magnitudes: Vec<f64> = weights.iter().map(|(_, _, w)| w.abs()).filter(|w| *w > 0.0).collect();
+            magnitudes.sort_by(f64::total_cmp);
+            let n = round(fraction * magnitudes.len() as f64) as usize;
+            // i pesi a pari merito con l'ultimo potato vengono potati con lui, per non dipendere dall'ordine
+            match n {
+                0 => 0.0,
+                n => f64::from_bits(magnitudes[n - 1].to_bits() + 1),
+            }
+        }
+    };
+    let mut pruned = 0;
+    weights.map_values(|w| {
+        if *w != 0.0 && w.abs() < threshold {
+            *w = 0.0;
+            pruned += 1;
+        }
+    });
+    pruned
+}
+
+fn quantize(weights: &mut Csr<f64>, bits: u32, scale: f64, error: &mut QuantizationError) {
+    let levels = ((1u64 << (bits - 1)) - 1) as f64;
+    weights.map_values(|w| {
+        if *w != 0.0 {
+            let quantized = round(*w / scale).clamp(-levels, levels) * scale;
+            error.add(*w, quantized, false);
+            *w = quantized;
+        }
+    });
+}
+
+fn max_abs(weights: &Csr<f64>) -> f64 {
+    weights.iter().map(|(_, _, w)| w.abs()).fold(0.0, f64::max)
+}
+
+pub fn compress_parameters(parameters: &mut serde_json::Value, config: &CompressionConfig) -> Result<CompressionReport, SNNError> {
+    /*
+     * Applica la trasformazione a input_weights e intra_layer_weights dei parametri letti da un file JSON della rete. Le
+     * matrici dense restano dense, quelle sparse vengono riscritte come triplette senza gli elementi nulli (e senza il file
+     * binario), tranne input_weights[0] che mantiene come zeri espliciti i pesi potati, così ogni input resta collegato agli
+     * stessi neuroni; tutti gli altri campi restano invariati.
+     */
+    config.check()?;
+    // (campo, indice, layer, matrice)
+    let mut matrices = vec![];
+    for field in ["input_weights", "intra_layer_weights"] {
+        let specs = match parameters.get(field).and_then(|m| m.as_array()) {
+            None => return Err(SNNError::BadFormatError(format!("The network has no {field} array"))),
+            Some(specs) => specs,
+        };
+        for (l, spec) in specs.iter().enumerate() {
+            let spec: MatrixSpec<f64> = match serde_json::from_value(spec.clone()) {
+                Err(e) => return Err(SNNError::BadFormatError(format!("{field}[{l}]: {e}"))),
+                Ok(s) => s,
+            };
+            matrices.push((field, l, spec.load(true)?));
+        }
+    }
+
+    let mut summaries = vec![];
+    for (field, l, weights) in matrices.iter_mut() {
+        let nonzero_before = weights.iter().filter(|(_, _, w)| *w != 0.0).count();
+        let pruned = config.pruning.map_or(0, |pruning| prune(weights, pruning));
+        summaries.push(MatrixSummary {
+            name: format!("{field}[{l}]"),
+            layer: *l,
+            entries: weights.rows() * weights.cols(),
+            nonzero_before,
+            nonzero_after: 0,
+            pruned,
+            scale: None,
+            error: QuantizationError::new(&format!("{field}[{l}]")),
+        });
+    }
+
+    if let Some(bits) = config.bits {
+        let levels = ((1u64 << (bits - 1)) - 1) as f64;
+        for m in 0..matrices.len() {
+            // massimo modulo sui pesi che condividono la scala
+            let range = match config.scale {
+                ScaleGranularity::Matrix => max_abs(&matrices[m].2),
+                ScaleGranularity::Layer => matrices.iter().filter(|(_, l, _)| *l == matrices[m].1).map(|(_, _, w)| max_abs(w)).fold(0.0, f64::max),
+            };
+            if range > 0.0 {
+                let scale = range / levels;
+                quantize(&mut matrices[m].2, bits, scale, &mut summaries[m].error);
+                summaries[m].scale = Some(scale);
+            }
+        }
+    }
+
+    for ((field, l, weights), summary) in matrices.iter().zip(summaries.iter_mut()) {
+        summary.nonzero_after = weights.iter().filter(|(_, _, w)| *w != 0.0).count();
+        let spec = &mut parameters[*field][*l];
+        if spec.is_array() {
+            let mut dense = vec![vec![0.0; weights.cols()]; weights.rows()];
+            for (i, j, w) in weights.iter() {
+                dense[i][j] = w;
+            }
+            *spec = json!(dense);
+        } else if let Some(fields) = spec.as_object_mut() {
+            let keep_zeros = *field == "input_weights" && *l == 0;
+            let triplets: Vec<(usize, usize, f64)> = weights.iter().filter(|(_, _, w)| keep_zeros || *w != 0.0).collect();
+            fields.remove("file");
+            fields.insert("triplets".to_string(), json!(triplets));
+        }
+    }
+    Ok(CompressionReport { matrices: summaries })
+}
+
+pub fn compress_json(input: &str, output: &str, config: &CompressionConfig) -> Result<CompressionReport, SNNError> {
+    // legge la rete da input, la trasforma e la scrive in output, caricabile con NeuralNetwork::from_json
+    let file = match File::open(input) {
+        Err(_) => return Err(SNNError::FileError(format!("Cannot open file {input}."))),
+        Ok(f) => f,
+    };
+    let mut parameters: serde_json::Value = match serde_json::from_reader(file) {
+        Err(e) => return Err(SNNError::BadFormatError(format!("File :{input}\nERROR:{e}"))),
+        Ok(p) => p,
+    };
+    let report = compress_parameters(&mut parameters, config)?;
+    let output_file = match File::create(output) {
+        Err(_) => return Err(SNNError::FileError(format!("Cannot create file {output}."))),
+        Ok(f) => f,
+    };
+    match serde_json::to_writer_pretty(output_file, &parameters) {
+        Err(e) => Err(SNNError::FileError(format!("File :{output}\nERROR:{e}"))),
+        Ok(_) => Ok(report),
+    }
+}
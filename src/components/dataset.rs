@@ -0,0 +1,94 @@
+use std::path::Path;
+
+use super::encoder::{parse_values, Encoding};
+use super::errors::SNNError;
+use super::input_layer::InputLayer;
+
+/*
+Campione di un dataset etichettato: un treno di spike per ogni input della rete e, se nota, la classe di appartenenza.
+*/
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub spikes: Vec<Vec<i8>>,
+    pub label: Option<usize>,
+}
+
+impl Sample {
+    pub fn new(spikes: Vec<Vec<i8>>, label: Option<usize>) -> Self {
+        Self { spikes, label }
+    }
+
+    pub fn from_file(path: &str, label: Option<usize>) -> Result<Self, SNNError> {
+        // legge un campione nello stesso formato di InputLayer::from_file, una riga per input
+        let il = InputLayer::from_file(path, '\n')?;
+        Ok(Self::new(il.inputs.iter().map(|input| input.spikes().to_vec()).collect(), label))
+    }
+
+    pub fn from_values(values: &[f64], label: Option<usize>, encoding: &Encoding) -> Self {
+        // codifica un vettore di valori analogici, un treno di spike per valore, con il generatore inizializzato dal seme
+        Self::new(encoding.encode_all(&[values.to_vec()]).remove(0), label)
+    }
+
+    pub fn train(&self, input: usize, presentation: usize, rest: usize) -> Vec<i8> {
+        // treno di spike dell'input lungo esattamente presentation passi, seguito da rest passi senza spike
+        let mut train: Vec<i8> = self.spikes[input].iter().copied().chain(std::iter::repeat(0)).take(presentation).collect();
+        train.resize(presentation + rest, 0);
+        train
+    }
+}
+
+pub fn load_dataset(index: &str) -> Result<Vec<Sample>, SNNError> {
+    /*
+     * Legge un dataset da un file indice con una riga per campione: il percorso del file del campione (relativo alla cartella
+     * dell'indice) seguito, opzionalmente, dall'etichetta. Le righe vuote vengono ignorate.
+     */
+    let content = match std::fs::read_to_string(index) {
+        Err(_) => return Err(SNNError::FileError(format!("Cannot open file {index}."))),
+        Ok(c) => c,
+    };
+    let dir = Path::new(index).parent().unwrap_or(Path::new(""));
+    let mut samples = vec![];
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let file = match fields.next() {
+            None => continue,
+            Some(f) => dir.join(f),
+        };
+        let label = match fields.next().map(|l| l.parse::<usize>()) {
+            None => None,
+            Some(Ok(l)) => Some(l),
+            Some(Err(_)) => return Err(SNNError::BadFormatError(format!("Invalid label in {index}: {line}"))),
+        };
+        samples.push(Sample::from_file(&file.to_string_lossy(), label)?);
+    }
+    Ok(samples)
+}
+
+pub fn load_analog_dataset(path: &str, encoding: &Encoding, labelled: bool) -> Result<Vec<Sample>, SNNError> {
+    /*
+     * Legge un dataset di valori analogici con una riga per campione (valori separati da virgole o spazi, come nei CSV di MNIST)
+     * e codifica ogni campione con encoding. Se labelled il primo valore della riga è l'etichetta. Tutti i campioni usano lo
+     * stesso generatore in sequenza, quindi il dataset codificato dipende solo dal seme.
+     */
+    let content = match std::fs::read_to_string(path) {
+        Err(_) => return Err(SNNError::FileError(format!("Cannot open file {path}."))),
+        Ok(c) => c,
+    };
+    let mut labels = vec![];
+    let mut rows = vec![];
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let mut values = parse_values(line)?;
+        let label = if labelled {
+            let label = values.remove(0);
+            if label < 0.0 || label.fract() != 0.0 {
+                return Err(SNNError::BadFormatError(format!("Invalid label in {path}: {line}")));
+            }
+            Some(label as usize)
+        } else {
+            None
+        };
+        labels.push(label);
+        rows.push(values);
+    }
+    Ok(encoding.encode_all(&rows).into_iter().zip(labels).map(|(spikes, label)| Sample::new(spikes, label)).collect())
+}
@@ -0,0 +1,268 @@
+use libm::{exp, log, round};
+use serde::Deserialize;
+
+use super::errors::SNNError;
+use super::rng::Rng;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateProcess {
+    // processo di Poisson: in un passo lungo dt arriva almeno un evento con probabilità 1 - exp(-rate * dt)
+    #[default]
+    Poisson,
+    // un tentativo per passo con probabilità rate * dt, saturata a 1
+    Bernoulli,
+}
+
+/*
+Codifica in frequenza di valori analogici: ogni valore, normalizzato in [0, 1] rispetto all'intervallo [min, max], diventa un
+treno di steps passi con frequenza media value * max_rate (in Hz, con passi lunghi dt millisecondi). Con lo stesso seme la
+codifica è riproducibile.
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct RateEncoder {
+    #[serde(default)]
+    pub process: RateProcess,
+    pub steps: usize,
+    pub max_rate: f64,
+    #[serde(default = "dt")]
+    pub dt: f64,
+    // intervallo dei valori in ingresso, ad esempio [0, 255] per i pixel di un'immagine a 8 bit
+    #[serde(default)]
+    pub min: f64,
+    #[serde(default = "max")]
+    pub max: f64,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+fn dt() -> f64 {
+    1.0
+}
+
+fn max() -> f64 {
+    1.0
+}
+
+impl RateEncoder {
+    pub fn new(process: RateProcess, steps: usize, max_rate: f64, seed: u64) -> Self {
+        Self { process, steps, max_rate, dt: dt(), min: 0.0, max: max(), seed }
+    }
+
+    pub fn probability(&self, value: f64) -> f64 {
+        // probabilità di emettere una spike in un passo per il valore dato
+        let expected = normalize(value, self.min, self.max) * self.max_rate * self.dt / 1000.0;
+        match self.process {
+            RateProcess::Poisson => 1.0 - exp(-expected),
+            RateProcess::Bernoulli => expected.min(1.0),
+        }
+    }
+
+    pub fn encode(&self, values: &[f64], rng: &mut Rng) -> Vec<Vec<i8>> {
+        // un treno di spike per valore, i passi di tutti i treni usano lo stesso generatore in sequenza
+        values
+            .iter()
+            .map(|value| {
+                let p = self.probability(*value);
+                (0..self.steps).map(|_| i8::from(rng.next_f64() < p)).collect()
+            })
+            .collect()
+    }
+}
+
+fn normalize(value: f64, min: f64, max: f64) -> f64 {
+    // valore riportato in [0, 1] rispetto all'intervallo [min, max]
+    let range = max - min;
+    if range > 0.0 { ((value - min) / range).clamp(0.0, 1.0) } else { 0.0 }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyScale {
+    // t = (1 - x) * (steps - 1): il valore massimo scatta al primo passo, quelli vicini alla soglia all'ultimo
+    #[default]
+    Linear,
+    // t = tau * ln(x / (x - threshold)), come la carica di una membrana RC con corrente costante x; con threshold non positiva
+    // la carica scatterebbe sempre al passo 0 e si usa t = tau * ln(1 / x)
+    Log,
+}
+
+/*
+Codifica a latenza (time-to-first-spike): ogni valore normalizzato x in [0, 1] diventa un treno di steps passi con al più una
+spike, tanto più anticipata quanto più x è grande. I valori non superiori a threshold non emettono spike, i tempi oltre
+l'ultimo passo vengono saturati a steps - 1.
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct LatencyEncoder {
+    #[serde(default)]
+    pub scale: LatencyScale,
+    pub steps: usize,
+    // costante di tempo in passi della codifica logaritmica
+    #[serde(default = "tau")]
+    pub tau: f64,
+    #[serde(default)]
+    pub threshold: f64,
+    #[serde(default)]
+    pub min: f64,
+    #[serde(default = "max")]
+    pub max: f64,
+}
+
+fn tau() -> f64 {
+    5.0
+}
+
+impl LatencyEncoder {
+    pub fn new(scale: LatencyScale, steps: usize) -> Self {
+        Self { scale, steps, tau: tau(), threshold: 0.0, min: 0.0, max: max() }
+    }
+
+    pub fn latency(&self, value: f64) -> Option<usize> {
+        // passo (da 0) della spike del valore dato, None se il valore non supera la soglia
+        let x = normalize(value, self.min, self.max);
+        if x <= self.threshold || self.steps == 0 {
+            return None;
+        }
+        let last = (self.steps - 1) as f64;
+        let t = match self.scale {
+            LatencyScale::Linear => (1.0 - x) * last,
+            LatencyScale::Log if self.threshold > 0.0 => self.tau * log(x / (x - self.threshold)),
+            LatencyScale::Log => self.tau * log(1.0 / x),
+        };
+        Some(round(t.min(last)) as usize)
+    }
+
+    pub fn encode(&self, values: &[f64]) -> Vec<Vec<i8>> {
+        values
+            .iter()
+            .map(|value| {
+                let mut train = vec![0; self.steps];
+                if let Some(t) = self.latency(*value) {
+                    train[t] = 1;
+                }
+                train
+            })
+            .collect()
+    }
+}
+
+/*
+Codifica a variazione (delta modulation) di segnali nel tempo, un campione per passo: ogni canale produce due treni, ON e OFF,
+negli input 2c e 2c + 1. Il primo campione fissa il livello di riferimento; a ogni passo successivo, se il segnale supera il
+riferimento di più di threshold viene emessa una spike ON, se scende di più di threshold una spike OFF, e il riferimento
+diventa il valore corrente. Al primo passo non vengono emesse spike.
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct DeltaEncoder {
+    pub threshold: f64,
+    // se false viene prodotto solo il treno ON di ogni canale
+    #[serde(default = "off")]
+    pub off: bool,
+}
+
+fn off() -> bool {
+    true
+}
+
+impl DeltaEncoder {
+    pub fn new(threshold: f64) -> Self {
+        Self { threshold, off: off() }
+    }
+
+    pub fn encode(&self, signals: &[Vec<f64>]) -> Vec<Vec<i8>> {
+        // un segnale per canale, i treni hanno la stessa lunghezza del segnale
+        let mut trains = vec![];
+        for signal in signals {
+            let mut on = vec![0; signal.len()];
+            let mut off = vec![0; signal.len()];
+            if let Some(first) = signal.first() {
+                let mut reference = *first;
+                for (t, value) in signal.iter().enumerate().skip(1) {
+                    if value - reference > self.threshold {
+                        on[t] = 1;
+                        reference = *value;
+                    } else if reference - value > self.threshold {
+                        off[t] = 1;
+                        reference = *value;
+                    }
+                }
+            }
+            trains.push(on);
+            if self.off {
+                trains.push(off);
+            }
+        }
+        trains
+    }
+}
+
+/*
+Codifica degli ingressi analogici, letta dal campo "encoder" del file JSON e distinta dal campo "type". Le codifiche rate e
+latency trasformano un vettore con un valore per input, la codifica delta un segnale nel tempo per canale.
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Encoding {
+    Rate(RateEncoder),
+    Latency(LatencyEncoder),
+    Delta(DeltaEncoder),
+}
+
+impl Encoding {
+    pub fn seed(&self) -> u64 {
+        // solo la codifica in frequenza è stocastica
+        match self {
+            Encoding::Rate(encoder) => encoder.seed,
+            Encoding::Latency(_) | Encoding::Delta(_) => 0,
+        }
+    }
+
+    pub fn encode(&self, values: &[f64], rng: &mut Rng) -> Vec<Vec<i8>> {
+        // per la codifica delta values è il segnale di un unico canale (ad esempio una riga di un dataset di serie temporali)
+        match self {
+            Encoding::Rate(encoder) => encoder.encode(values, rng),
+            Encoding::Latency(encoder) => encoder.encode(values),
+            Encoding::Delta(encoder) => encoder.encode(&[values.to_vec()]),
+        }
+    }
+
+    pub fn encode_file(&self, path: &str) -> Result<Vec<Vec<i8>>, SNNError> {
+        // codifica il file degli ingressi: un valore per input, oppure per la codifica delta un segnale per riga
+        match self {
+            Encoding::Delta(encoder) => Ok(encoder.encode(&read_signals(path)?)),
+            _ => Ok(self.encode_all(&[read_values(path)?]).remove(0)),
+        }
+    }
+
+    pub fn encode_all(&self, samples: &[Vec<f64>]) -> Vec<Vec<Vec<i8>>> {
+        // codifica più campioni con un unico generatore inizializzato dal seme, nell'ordine dato
+        let mut rng = Rng::new(self.seed());
+        samples.iter().map(|values| self.encode(values, &mut rng)).collect()
+    }
+}
+
+pub fn parse_values(content: &str) -> Result<Vec<f64>, SNNError> {
+    // valori analogici separati da spazi, virgole o a capo
+    content
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|field| !field.is_empty())
+        .map(|field| field.parse::<f64>().map_err(|_| SNNError::BadFormatError(format!("Invalid analog value: {field}"))))
+        .collect()
+}
+
+pub fn read_values(path: &str) -> Result<Vec<f64>, SNNError> {
+    // legge un file con un valore analogico per input
+    match std::fs::read_to_string(path) {
+        Err(_) => Err(SNNError::FileError(format!("Cannot open file {path}."))),
+        Ok(content) => parse_values(&content),
+    }
+}
+
+pub fn read_signals(path: &str) -> Result<Vec<Vec<f64>>, SNNError> {
+    // legge un segnale per riga, con i campioni separati da spazi o virgole, ignorando le righe vuote
+    let content = match std::fs::read_to_string(path) {
+        Err(_) => return Err(SNNError::FileError(format!("Cannot open file {path}."))),
+        Ok(c) => c,
+    };
+    content.lines().filter(|line| !line.trim().is_empty()).map(parse_values).collect()
+}
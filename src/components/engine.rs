@@ -0,0 +1,39 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use super::errors::SNNError;
+
+/*
+Motore usato da NeuralNetwork::run per simulare la rete. Tutti i motori producono gli stessi conteggi nell'output monitor:
+- threaded: un thread per ogni input e per ogni neurone e un channel per ogni sinapsi, che riceve una spike (anche nulla) a
+  ogni passo; è il motore di riferimento e il suo costo cresce con il numero totale di sinapsi;
+- clock_driven: un solo thread che avanza tutta la rete un passo alla volta, consegnando le spike sulla connettività in uscita
+  in formato CSR (vedi clock_engine::Fanout) delle sole sorgenti che hanno emesso;
+- event_driven: un solo thread che aggiorna solo i neuroni che ricevono spike, estratte da una coda con priorità;
+- parallel: un pool fisso di worker che aggiorna in parallelo porzioni di ogni layer a ogni passo, con la stessa consegna del
+  motore clock_driven.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Engine {
+    #[default]
+    Threaded,
+    ClockDriven,
+    EventDriven,
+    Parallel,
+}
+
+impl FromStr for Engine {
+    type Err = SNNError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "threaded" => Ok(Engine::Threaded),
+            "clock" | "clock_driven" => Ok(Engine::ClockDriven),
+            "event" | "event_driven" => Ok(Engine::EventDriven),
+            "parallel" => Ok(Engine::Parallel),
+            _ => Err(SNNError::EngineError(format!("Unknown engine {s}, use threaded, clock_driven, event_driven or parallel"))),
+        }
+    }
+}
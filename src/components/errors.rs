@@ -1,3 +1,5 @@
+use std::any::Any;
+
 #[derive(Debug)]
 
 
@@ -21,6 +23,20 @@ pub enum SNNError {
     /* Bad formatting file error */
     BadFormatError(String),
 
-    FileError(String)
+    FileError(String),
+
+    /* The network cannot be simulated by the selected engine (e.g. a cycle of same-step dependencies) */
+    EngineError(String)
     
 }
+
+impl SNNError {
+    pub fn from_panic(thread: &str, payload: Box<dyn Any + Send>) -> Self {
+        // errore di un thread terminato con un panic, con il messaggio del panic se è una stringa
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => payload.downcast_ref::<&str>().map_or("unknown panic".to_string(), |m| m.to_string()),
+        };
+        SNNError::EngineError(format!("The {thread} thread panicked: {message}"))
+    }
+}
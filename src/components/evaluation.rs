@@ -0,0 +1,356 @@
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use super::errors::SNNError;
+use super::output::OutputRecord;
+
+/*
+Regola usata quando più neuroni di output hanno lo stesso numero massimo di spike.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TieBreak {
+    // vince il neurone con indice minore
+    #[default]
+    Lowest,
+    // vince il neurone con indice maggiore
+    Highest,
+    // nessuna classe, il campione viene contato come rifiutato
+    Reject,
+}
+
+/*
+Comportamento quando nessun neurone di output emette spike.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoSpike {
+    // nessuna classe, il campione viene contato come rifiutato
+    #[default]
+    Reject,
+    // tutti i neuroni sono a pari merito, decide tie_break
+    TieBreak,
+    // classe fissa
+    Class(usize),
+}
+
+/*
+Decodifica per conteggio: la classe di un campione è l'indice del neurone di output con più spike.
+*/
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct CountDecoder {
+    #[serde(default)]
+    pub tie_break: TieBreak,
+    #[serde(default)]
+    pub no_spike: NoSpike,
+}
+
+impl CountDecoder {
+    pub fn new(tie_break: TieBreak, no_spike: NoSpike) -> Self {
+        Self { tie_break, no_spike }
+    }
+
+    pub fn decode(&self, counts: &[i32]) -> Option<usize> {
+        // classe decodificata dai conteggi di un campione, None se il campione viene rifiutato
+        let max = *counts.iter().max()?;
+        if max == 0 {
+            match self.no_spike {
+                NoSpike::Reject => return None,
+                NoSpike::Class(class) => return Some(class),
+                NoSpike::TieBreak => {}
+            }
+        }
+        break_tie(self.tie_break, counts.iter().enumerate().filter(|(_, c)| **c == max).map(|(i, _)| i))
+    }
+}
+
+/*
+Decodifica per latenza: la classe di un campione è l'indice del neurone di output che emette la prima spike. Adatta ai codici a
+latenza, in cui ogni neurone emette al più una spike e i conteggi non distinguono le classi.
+*/
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct FirstSpikeDecoder {
+    #[serde(default)]
+    pub tie_break: TieBreak,
+    #[serde(default)]
+    pub no_spike: NoSpike,
+}
+
+impl FirstSpikeDecoder {
+    pub fn new(tie_break: TieBreak, no_spike: NoSpike) -> Self {
+        Self { tie_break, no_spike }
+    }
+
+    pub fn decode(&self, first_spikes: &[Option<usize>]) -> Option<usize> {
+        // classe decodificata dai passi della prima spike di ogni neurone, None se il campione viene rifiutato
+        match first_spikes.iter().flatten().min() {
+            Some(earliest) => break_tie(
+                self.tie_break,
+                first_spikes.iter().enumerate().filter(|(_, t)| **t == Some(*earliest)).map(|(i, _)| i),
+            ),
+            None => match self.no_spike {
+                NoSpike::Reject => None,
+                NoSpike::Class(class) => Some(class),
+                NoSpike::TieBreak => break_tie(self.tie_break, 0..first_spikes.len()),
+            },
+        }
+    }
+
+    pub fn rank_order(&self, first_spikes: &[Option<usize>]) -> Vec<usize> {
+        // decodifica rank-order: neuroni che hanno emesso almeno una spike, dal primo all'ultimo. A parità di passo l'ordine
+        // segue tie_break (indice crescente se Lowest o Reject, decrescente se Highest)
+        let mut fired: Vec<(usize, usize)> = first_spikes.iter().enumerate().filter_map(|(n, t)| t.map(|t| (t, n))).collect();
+        match self.tie_break {
+            TieBreak::Highest => fired.sort_by_key(|(t, n)| (*t, std::cmp::Reverse(*n))),
+            _ => fired.sort(),
+        }
+        fired.into_iter().map(|(_, n)| n).collect()
+    }
+}
+
+/*
+Decodifica usata per valutare i risultati di run_batch_records, letta anche da JSON con il campo "type".
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Decoding {
+    Count(CountDecoder),
+    FirstSpike(FirstSpikeDecoder),
+}
+
+impl Default for Decoding {
+    fn default() -> Self {
+        Decoding::Count(CountDecoder::default())
+    }
+}
+
+impl Decoding {
+    pub fn decode(&self, record: &OutputRecord) -> Option<usize> {
+        match self {
+            Decoding::Count(decoder) => decoder.decode(&record.counts),
+            Decoding::FirstSpike(decoder) => decoder.decode(&record.first_spikes),
+        }
+    }
+}
+
+fn break_tie(tie_break: TieBreak, mut winners: impl DoubleEndedIterator<Item = usize>) -> Option<usize> {
+    // sceglie tra i neuroni a pari merito (in ordine di indice)
+    let first = winners.next()?;
+    match (tie_break, winners.next_back()) {
+        (_, None) => Some(first),
+        (TieBreak::Lowest, Some(_)) => Some(first),
+        (TieBreak::Highest, Some(last)) => Some(last),
+        (TieBreak::Reject, Some(_)) => None,
+    }
+}
+
+pub fn load_labels(path: &str) -> Result<Vec<usize>, SNNError> {
+    // legge un file di etichette, una per riga nello stesso ordine dei campioni, ignorando le righe vuote
+    let content = match std::fs::read_to_string(path) {
+        Err(_) => return Err(SNNError::FileError(format!("Cannot open file {path}."))),
+        Ok(c) => c,
+    };
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.parse::<usize>() {
+            Ok(label) => Ok(label),
+            Err(_) => Err(SNNError::BadFormatError(format!("Invalid label in {path}: {line}"))),
+        })
+        .collect()
+}
+
+/*
+Risultato della classificazione di un insieme di campioni: matrice di confusione (righe: classe vera, colonne: classe
+decodificata) e numero di campioni rifiutati per ogni classe vera.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation {
+    confusion: Vec<Vec<usize>>,
+    rejected: Vec<usize>,
+}
+
+impl Evaluation {
+    pub fn new(n_classes: usize) -> Self {
+        Self {
+            confusion: vec![vec![0; n_classes]; n_classes],
+            rejected: vec![0; n_classes],
+        }
+    }
+
+    pub fn from_predictions(labels: &[usize], predictions: &[Option<usize>]) -> Result<Self, SNNError> {
+        // il numero di classi è il massimo tra le etichette e le classi decodificate
+        if labels.len() != predictions.len() {
+            return Err(SNNError::BadFormatError(format!("{} labels for {} samples", labels.len(), predictions.len())));
+        }
+        let n_classes = labels.iter().chain(predictions.iter().flatten()).max().map_or(0, |c| c + 1);
+        let mut evaluation = Self::new(n_classes);
+        for (label, prediction) in labels.iter().zip(predictions) {
+            evaluation.add(*label, *prediction);
+        }
+        Ok(evaluation)
+    }
+
+    pub fn from_counts(counts: &[Vec<i32>], labels: &[usize], decoder: &CountDecoder) -> Result<Self, SNNError> {
+        // decodifica i conteggi di ogni campione (per esempio il risultato di run_batch) e li confronta con le etichette
+        let predictions: Vec<Option<usize>> = counts.iter().map(|c| decoder.decode(c)).collect();
+        let mut evaluation = Self::from_predictions(labels, &predictions)?;
+        // anche le classi che non compaiono mai fanno parte del report
+        let n_outputs = counts.iter().map(|c| c.len()).max().unwrap_or(0);
+        while evaluation.n_classes() < n_outputs {
+            evaluation.add_class();
+        }
+        Ok(evaluation)
+    }
+
+    pub fn from_records(records: &[OutputRecord], labels: &[usize], decoding: &Decoding) -> Result<Self, SNNError> {
+        // come from_counts, con una decodifica qualsiasi dei risultati di run_batch_records
+        let predictions: Vec<Option<usize>> = records.iter().map(|r| decoding.decode(r)).collect();
+        let mut evaluation = Self::from_predictions(labels, &predictions)?;
+        let n_outputs = records.iter().map(|r| r.counts.len()).max().unwrap_or(0);
+        while evaluation.n_classes() < n_outputs {
+            evaluation.add_class();
+        }
+        Ok(evaluation)
+    }
+
+    fn add_class(&mut self) {
+        for row in self.confusion.iter_mut() {
+            row.push(0);
+        }
+        self.confusion.push(vec![0; self.confusion.len() + 1]);
+        self.rejected.push(0);
+    }
+
+    pub fn add(&mut self, label: usize, prediction: Option<usize>) {
+        // aggiunge un campione, le classi mancanti vengono aggiunte alla matrice
+        while self.n_classes() <= label.max(prediction.unwrap_or(0)) {
+            self.add_class();
+        }
+        match prediction {
+            None => self.rejected[label] += 1,
+            Some(class) => self.confusion[label][class] += 1,
+        }
+    }
+
+    pub fn n_classes(&self) -> usize {
+        self.confusion.len()
+    }
+
+    pub fn samples(&self) -> usize {
+        self.confusion.iter().flatten().sum::<usize>() + self.rejected.iter().sum::<usize>()
+    }
+
+    pub fn confusion_matrix(&self) -> &[Vec<usize>] {
+        &self.confusion
+    }
+
+    pub fn rejected(&self) -> usize {
+        self.rejected.iter().sum()
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        // i campioni rifiutati contano come errori
+        let correct: usize = (0..self.n_classes()).map(|c| self.confusion[c][c]).sum();
+        match self.samples() {
+            0 => 0.0,
+            n => correct as f64 / n as f64,
+        }
+    }
+
+    pub fn precision(&self, class: usize) -> Option<f64> {
+        // frazione dei campioni decodificati come class che appartengono a class, None se nessun campione è decodificato così
+        let predicted: usize = self.confusion.iter().map(|row| row[class]).sum();
+        match predicted {
+            0 => None,
+            n => Some(self.confusion[class][class] as f64 / n as f64),
+        }
+    }
+
+    pub fn recall(&self, class: usize) -> Option<f64> {
+        // frazione dei campioni di class decodificati correttamente, None se la classe non ha campioni
+        match self.support(class) {
+            0 => None,
+            n => Some(self.confusion[class][class] as f64 / n as f64),
+        }
+    }
+
+    pub fn support(&self, class: usize) -> usize {
+        // numero di campioni della classe, rifiutati compresi
+        self.confusion[class].iter().sum::<usize>() + self.rejected[class]
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let classes: Vec<serde_json::Value> = (0..self.n_classes())
+            .map(|c| {
+                json!({
+                    "class": c,
+                    "precision": self.precision(c),
+                    "recall": self.recall(c),
+                    "support": self.support(c),
+                    "rejected": self.rejected[c],
+                })
+            })
+            .collect();
+        json!({
+            "samples": self.samples(),
+            "accuracy": self.accuracy(),
+            "rejected": self.rejected(),
+            "classes": classes,
+            "confusion_matrix": self.confusion,
+        })
+    }
+
+    pub fn save(&self, text_path: &str, json_path: &str) -> Result<(), SNNError> {
+        // scrive il report in formato testo e JSON
+        let mut text_file = match File::create(text_path) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot create file {text_path}."))),
+            Ok(f) => f,
+        };
+        if let Err(e) = write!(text_file, "{}", self) {
+            return Err(SNNError::FileError(format!("File :{text_path}\nERROR:{e}")));
+        }
+        let json_file = match File::create(json_path) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot create file {json_path}."))),
+            Ok(f) => f,
+        };
+        match serde_json::to_writer_pretty(json_file, &self.to_json()) {
+            Err(e) => Err(SNNError::FileError(format!("File :{json_path}\nERROR:{e}"))),
+            Ok(_) => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |x: Option<f64>| x.map_or("-".to_string(), |x| format!("{:.2}%", 100.0 * x));
+        writeln!(f, "samples: {}", self.samples())?;
+        writeln!(f, "accuracy: {}", percent(Some(self.accuracy())))?;
+        writeln!(f, "rejected: {}", self.rejected())?;
+        writeln!(f)?;
+        writeln!(f, "{:>6} {:>10} {:>10} {:>8}", "class", "precision", "recall", "support")?;
+        for c in 0..self.n_classes() {
+            writeln!(f, "{:>6} {:>10} {:>10} {:>8}", c, percent(self.precision(c)), percent(self.recall(c)), self.support(c))?;
+        }
+        writeln!(f)?;
+        // matrice di confusione, l'ultima colonna contiene i campioni rifiutati
+        write!(f, "{:>6}", "true")?;
+        for c in 0..self.n_classes() {
+            write!(f, " {:>6}", c)?;
+        }
+        writeln!(f, " {:>6}", "none")?;
+        for (c, row) in self.confusion.iter().enumerate() {
+            write!(f, "{:>6}", c)?;
+            for n in row {
+                write!(f, " {:>6}", n)?;
+            }
+            writeln!(f, " {:>6}", self.rejected[c])?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use super::{input_layer::InputLayer, neural_layer::NeuralLayer, output::{OutputMonitor, OutputRecord}, spike::Spike, errors::SNNError};
+use super::clock_engine::{finish, firing_order, update, Fanout};
+use super::synapse::Projection;
+use super::raster::{RasterTarget, SpikeRaster};
+
+// evento in coda: (passo, layer, posizione del neurone nell'ordine di aggiornamento del layer, (sinapsi, spike)). None indica un
+// risveglio senza spike per i neuroni che vanno aggiornati a ogni passo e precede gli eventi dello stesso neurone
+type Event = Reverse<(usize, usize, usize, Option<(usize, i8)>)>;
+
+/*
+Motore event-driven in un solo thread. Le spike vengono inserite, seguendo la connettività in uscita di chi le ha emesse (vedi
+clock_engine::Fanout), in una coda con priorità ordinata per passo e, all'interno del passo, nello stesso ordine di
+aggiornamento del motore clock-driven, per cui le spike arrivano con la stessa temporizzazione:
+- una sinapsi con peso negativo (vedi Neuron::lag) consegna la spike al passo successivo a quello di emissione (lag 1), le
+  altre nello stesso passo;
+- un neurone termina al passo K in cui una delle sorgenti non ha più spike da inviare, K viene calcolato prima della
+  simulazione a partire dalla lunghezza degli input.
+Un neurone event-driven (vedi Neuron::is_event_driven) viene aggiornato solo nei passi in cui riceve spike, il tempo trascorso
+dall'ultimo aggiornamento viene recuperato con il decadimento in forma chiusa del modello. Gli altri neuroni vengono
+risvegliati a ogni passo come nel motore clock-driven.
+*/
+pub fn run(input_layer: &InputLayer, layers: &mut [NeuralLayer], mut output_monitor: OutputMonitor, rasters: &mut [SpikeRaster]) -> Result<OutputRecord, SNNError> {
+    let orders = layers.iter().map(firing_order).collect::<Result<Vec<_>, _>>()?;
+    // posizione di ogni neurone nell'ordine di aggiornamento del suo layer
+    let mut ranks: Vec<Vec<usize>> = layers.iter().map(|l| vec![0; l.neurons.len()]).collect();
+    for (l, order) in orders.iter().enumerate() {
+        for (rank, j) in order.iter().enumerate() {
+            ranks[l][*j] = rank;
+        }
+    }
+
+    // destinazioni delle spike di ogni input e di ogni neurone
+    let fanout = Fanout::new(input_layer, layers);
+
+    let last_ticks = last_ticks(input_layer, layers, &orders)?;
+    let horizon = last_ticks.iter().flatten().copied().max().unwrap_or(0);
+    let always_active: Vec<Vec<bool>> = layers.iter().map(|l| l.neurons.iter().map(|n| !n.is_event_driven()).collect()).collect();
+    // ultimo passo in cui è stato aggiornato ogni neurone event-driven
+    let mut updated: Vec<Vec<usize>> = layers.iter().map(|l| vec![0; l.neurons.len()]).collect();
+    let last_layer = layers.len() - 1;
+    // layer osservati da almeno un raster e neuroni che hanno emesso una spike nel passo corrente
+    let monitored: Vec<bool> = (0..layers.len()).map(|l| rasters.iter().any(|r| r.target() == RasterTarget::Layer(l))).collect();
+    let mut fired = vec![];
+
+    let mut queue: BinaryHeap<Event> = BinaryHeap::new();
+    for (l, order) in orders.iter().enumerate() {
+        for (rank, j) in order.iter().enumerate() {
+            if always_active[l][*j] && last_ticks[l][*j] >= 1 {
+                queue.push(Reverse((1, l, rank, None)));
+            }
+        }
+    }
+
+    for ts in 1..=horizon {
+        // le spike degli input al passo ts entrano in coda
+        for (i, input) in input_layer.inputs.iter().enumerate() {
+            let spike = input.spikes().get(ts - 1).copied().unwrap_or(0);
+            if spike != 0 {
+                for (_, j, k, lag) in fanout.input_targets(i) {
+                    queue.push(Reverse((ts + lag, 0, ranks[0][j], Some((k, spike)))));
+                }
+            }
+        }
+
+        while let Some(Reverse((t, l, rank, first))) = queue.peek().copied() {
+            if t != ts {
+                break;
+            }
+            // raccoglie tutti gli eventi dello stesso neurone, già ordinati per sinapsi
+            queue.pop();
+            let mut events = vec![];
+            if let Some(event) = first {
+                events.push(event);
+            }
+            while let Some(Reverse((t2, l2, rank2, Some(event)))) = queue.peek().copied() {
+                if (t2, l2, rank2) != (t, l, rank) {
+                    break;
+                }
+                queue.pop();
+                events.push(event);
+            }
+
+            let j = orders[l][rank];
+            let last_tick = last_ticks[l][j];
+            let neuron = &mut layers[l].neurons[j];
+            if ts >= last_tick {
+                if always_active[l][j] && ts == last_tick {
+                    // ultimo passo, le sinapsi ricevono le spike come nel motore clock-driven
+                    finish(neuron, l, j, ts, &mut events, input_layer, &last_ticks);
+                }
+                continue;
+            }
+
+            let out_spike = if always_active[l][j] {
+                // neurone aggiornato a ogni passo: le sinapsi senza eventi ricevono 0
+                queue.push(Reverse((ts + 1, l, rank, None)));
+                update(neuron, ts, &mut events, false)?
+            } else {
+                // salta i passi senza ingressi trascorsi dall'ultimo aggiornamento
+                let silent = ts - 1 - updated[l][j];
+                if silent > 0 {
+                    neuron.advance(silent as u32);
+                }
+                updated[l][j] = ts;
+                let weighted_inputs = neuron.collect_events(&events);
+                neuron.step(&weighted_inputs)
+            };
+
+            if out_spike != 0 {
+                for (tl, tj, k, lag) in fanout.targets(l, j) {
+                    if ts + lag <= last_ticks[tl][tj] {
+                        queue.push(Reverse((ts + lag, tl, ranks[tl][tj], Some((k, out_spike)))));
+                    }
+                }
+                if l == last_layer {
+                    output_monitor.record(Spike::new(out_spike, Some(j as i32)), ts)?;
+                }
+                if monitored[l] {
+                    fired.push((l, j));
+                }
+            }
+        }
+
+        // i neuroni vengono aggiornati in ordine topologico, il raster li registra in ordine di indice
+        fired.sort();
+        for (l, j) in fired.drain(..) {
+            for raster in rasters.iter_mut().filter(|r| r.target() == RasterTarget::Layer(l)) {
+                raster.record(ts, j);
+            }
+        }
+    }
+
+    // i neuroni event-driven recuperano i passi silenziosi fino all'ultimo aggiornamento del motore clock-driven
+    for (l, layer) in layers.iter_mut().enumerate() {
+        for (j, neuron) in layer.neurons.iter_mut().enumerate() {
+            let silent = last_ticks[l][j].saturating_sub(1 + updated[l][j]);
+            if !always_active[l][j] && silent > 0 {
+                neuron.advance(silent as u32);
+            }
+        }
+    }
+
+    Ok(output_monitor.into_record())
+}
+
+pub fn spike_of(events: &[(usize, i8)], k: usize) -> i8 {
+    // spike ricevuta dalla sinapsi k nel passo corrente, 0 se non c'è un evento
+    match events.binary_search_by_key(&k, |(synapse, _)| *synapse) {
+        Ok(e) => events[e].1,
+        Err(_) => 0,
+    }
+}
+
+pub fn last_ticks(input_layer: &InputLayer, layers: &[NeuralLayer], orders: &[Vec<usize>]) -> Result<Vec<Vec<usize>>, SNNError> {
+    /*
+     * Passo K in cui termina ogni neurone: al passo k una sinapsi con lag λ legge la spike k - λ, che esiste solo se la sorgente
+     * ne ha emesse almeno altrettante (le spike di un input sono la sua lunghezza, quelle di un neurone K, compreso lo 0 finale).
+     * Quindi K = min(emesse + λ) + 1 sulle sinapsi del neurone. Le sinapsi intra con lag 1 possono formare cicli, per cui il
+     * valore viene calcolato per rilassamento partendo da infinito. Un neurone senza sinapsi termina subito (K = 0).
+     */
+    let mut last: Vec<Vec<usize>> = layers
+        .iter()
+        .map(|l| l.neurons.iter().map(|n| if n.synapses.is_empty() { 0 } else { usize::MAX }).collect())
+        .collect();
+    for (l, layer) in layers.iter().enumerate() {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &j in &orders[l] {
+                let k = layer.neurons[j]
+                    .synapses
+                    .iter()
+                    .enumerate()
+                    .map(|(k, synapse)| {
+                        let lag = layer.neurons[j].lag(k);
+                        let i = synapse.get_source();
+                        let produced = match synapse.get_projection() {
+                            Projection::Input if l == 0 => input_layer.inputs[i].spikes().len(),
+                            Projection::Input => last[l - 1][i],
+                            Projection::Intra => last[l][i],
+                        };
+                        produced.saturating_add(lag + 1)
+                    })
+                    .min()
+                    .unwrap_or(0);
+                if k < last[l][j] {
+                    last[l][j] = k;
+                    changed = true;
+                }
+            }
+        }
+        if last[l].contains(&usize::MAX) {
+            return Err(SNNError::EngineError(format!("Some neurons of layer [{l}] do not depend on any input and would never stop")));
+        }
+    }
+    Ok(last)
+}
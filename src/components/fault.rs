@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+use super::hardware::{DatapathPort, DatapathUnit};
+
+/*
+Valore della rete colpito da un guasto: i parametri e il potenziale di membrana del modello di un neurone, il peso di una delle
+sue sinapsi oppure un segnale del datapath dei modelli con una vista hardware (vedi hardware::Datapath). Per i modelli diversi
+dal lif la soglia è la soglia (o il picco) del modello, il riposo il potenziale di leak o iniziale e il reset il potenziale dopo
+una spike.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultComponent {
+    VThreshold,
+    VRest,
+    VReset,
+    VMem,
+    Weight,
+    Datapath { unit: DatapathUnit, port: DatapathPort },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultKind {
+    // bit forzato a 0 o a 1 per tutta la simulazione (guasto permanente)
+    StuckAt0,
+    StuckAt1,
+    // bit invertito una sola volta al passo indicato (guasto transitorio), il valore corrotto resta finché non viene riscritto
+    BitFlip,
+}
+
+impl FaultKind {
+    pub fn apply(self, value: f64, bit: u8) -> f64 {
+        // applica il guasto al bit indicato della rappresentazione IEEE-754 del valore (0 è il bit meno significativo della
+        // mantissa, 63 il segno)
+        f64::from_bits(self.apply_bits(value.to_bits(), bit))
+    }
+
+    pub fn apply_bits(self, bits: u64, bit: u8) -> u64 {
+        let mask = 1u64 << bit;
+        match self {
+            FaultKind::StuckAt0 => bits & !mask,
+            FaultKind::StuckAt1 => bits | mask,
+            FaultKind::BitFlip => bits ^ mask,
+        }
+    }
+
+    pub fn is_permanent(self) -> bool {
+        self != FaultKind::BitFlip
+    }
+}
+
+/*
+Guasto su un singolo bit, letto dal campo "faults" del file JSON o aggiunto con NeuralNetwork::add_fault. synapse è l'indice
+della sinapsi all'interno del neurone ed è richiesto solo per i pesi; time è il passo (da 1, come nell'output monitor) in cui
+avviene un bit-flip, ignorato dai guasti permanenti. Un bit-flip su un valore memorizzato resta fino alla successiva scrittura,
+su un segnale del datapath vale solo nel passo indicato.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Fault {
+    pub component: FaultComponent,
+    pub layer: usize,
+    pub neuron: usize,
+    #[serde(default)]
+    pub synapse: Option<usize>,
+    pub bit: u8,
+    pub kind: FaultKind,
+    #[serde(default)]
+    pub time: usize,
+}
+
+impl Fault {
+    pub fn new(component: FaultComponent, layer: usize, neuron: usize, synapse: Option<usize>, bit: u8, kind: FaultKind, time: usize) -> Self {
+        Self { component, layer, neuron, synapse, bit, kind, time }
+    }
+
+    pub fn is_active(&self, tick: usize) -> bool {
+        // true se il guasto va applicato al passo tick
+        self.kind.is_permanent() || self.time == tick
+    }
+}
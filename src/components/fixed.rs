@@ -0,0 +1,308 @@
+use std::fmt;
+
+use libm::{exp, floor};
+use serde::Deserialize;
+
+use super::errors::SNNError;
+use super::fault::FaultComponent;
+use super::models::NeuronModel;
+use super::rng::Rng;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    // i valori fuori dall'intervallo rappresentabile vengono portati all'estremo più vicino
+    #[default]
+    Saturate,
+    // complemento a due: i bit oltre la larghezza del formato vengono scartati
+    Wrap,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    // scarta i bit meno significativi (arrotonda verso -inf)
+    Truncate,
+    // al valore rappresentabile più vicino, i casi a metà verso +inf
+    #[default]
+    Nearest,
+    // verso l'alto con probabilità pari alla frazione scartata, senza errore sistematico in media
+    Stochastic,
+}
+
+/*
+Formato Q con segno: un bit di segno, integer_bits bit di parte intera e fraction_bits bit di parte frazionaria. Il valore
+rappresentato è raw / 2^fraction_bits con raw intero in [-2^(integer_bits + fraction_bits), 2^(integer_bits + fraction_bits) - 1].
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct QFormat {
+    pub integer_bits: u32,
+    pub fraction_bits: u32,
+}
+
+impl QFormat {
+    pub fn new(integer_bits: u32, fraction_bits: u32) -> Self {
+        Self { integer_bits, fraction_bits }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        // il valore grezzo deve stare in un i64 e il prodotto di due valori in un i128
+        self.integer_bits + self.fraction_bits <= 62
+    }
+
+    pub fn resolution(&self) -> f64 {
+        // distanza tra due valori rappresentabili consecutivi
+        1.0 / (1u64 << self.fraction_bits) as f64
+    }
+
+    fn max_raw(&self) -> i128 {
+        (1i128 << (self.integer_bits + self.fraction_bits)) - 1
+    }
+
+    fn min_raw(&self) -> i128 {
+        -(1i128 << (self.integer_bits + self.fraction_bits))
+    }
+
+    pub fn overflow(&self, raw: i128, overflow: Overflow) -> i64 {
+        // riporta un valore grezzo nell'intervallo del formato
+        match overflow {
+            Overflow::Saturate => raw.clamp(self.min_raw(), self.max_raw()) as i64,
+            Overflow::Wrap => {
+                let width = self.integer_bits + self.fraction_bits + 1;
+                let shift = 128 - width;
+                ((raw << shift) >> shift) as i64
+            }
+        }
+    }
+
+    pub fn to_raw(&self, x: f64, overflow: Overflow, rounding: Rounding, rng: &mut Rng) -> i64 {
+        // quantizza un valore reale, NaN diventa 0 e gli infiniti vanno agli estremi
+        if x.is_nan() {
+            return 0;
+        }
+        let scaled = x * (1u64 << self.fraction_bits) as f64;
+        let low = floor(scaled);
+        let rounded = match rounding {
+            Rounding::Truncate => low,
+            Rounding::Nearest => floor(scaled + 0.5),
+            Rounding::Stochastic => low + f64::from(u8::from(rng.next_f64() < scaled - low)),
+        };
+        // i valori oltre il range di i128 vengono comunque saturati dalla conversione
+        let raw = if rounded.abs() < 1e38 { rounded as i128 } else if rounded > 0.0 { i128::MAX } else { i128::MIN };
+        match overflow {
+            // con wrap un valore enorme non ha bit significativi nella finestra del formato, viene saturato
+            Overflow::Wrap if !(-1e18..=1e18).contains(&rounded) => self.overflow(raw, Overflow::Saturate),
+            _ => self.overflow(raw, overflow),
+        }
+    }
+
+    pub fn to_f64(&self, raw: i64) -> f64 {
+        raw as f64 / (1u64 << self.fraction_bits) as f64
+    }
+
+    pub fn quantize(&self, x: f64, overflow: Overflow, rounding: Rounding, rng: &mut Rng) -> f64 {
+        // valore rappresentabile assegnato a x
+        self.to_f64(self.to_raw(x, overflow, rounding, rng))
+    }
+}
+
+fn shift_round(x: i128, shift: u32, rounding: Rounding, rng: &mut Rng) -> i128 {
+    // divide x per 2^shift arrotondando come indicato
+    if shift == 0 {
+        return x;
+    }
+    let low = x >> shift;
+    match rounding {
+        Rounding::Truncate => low,
+        Rounding::Nearest => (x + (1i128 << (shift - 1))) >> shift,
+        Rounding::Stochastic => {
+            let rest = (x - (low << shift)) as f64 / (1i128 << shift) as f64;
+            low + i128::from(rng.next_f64() < rest)
+        }
+    }
+}
+
+/*
+Modalità a virgola fissa della rete, letta dal campo "fixed_point" del file JSON: formati del potenziale di membrana (e dei
+parametri di tensione), dei pesi e del fattore di decadimento, comportamento in overflow e arrotondamento. Il seme inizializza
+l'arrotondamento stocastico, con una sequenza diversa per ogni neurone.
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct FixedPointConfig {
+    pub membrane: QFormat,
+    pub weight: QFormat,
+    pub decay: QFormat,
+    #[serde(default)]
+    pub overflow: Overflow,
+    #[serde(default)]
+    pub rounding: Rounding,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl FixedPointConfig {
+    pub fn check(&self) -> Result<(), SNNError> {
+        for (name, format) in [("membrane", self.membrane), ("weight", self.weight), ("decay", self.decay)] {
+            if !format.is_valid() {
+                return Err(SNNError::BadFormatError(format!("The {name} format has more than 62 integer and fraction bits")));
+            }
+        }
+        Ok(())
+    }
+}
+
+/*
+Lif in virgola fissa: stesso aggiornamento di models::lif, ma potenziale, parametri e ingressi sono valori del formato membrane
+e il fattore di decadimento exp(-elapsed / tau) è quantizzato nel formato decay (come una tabella in memoria). Ogni operazione
+arrotonda il risultato e applica l'overflow del formato.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct FixedLif {
+    config: FixedPointConfig,
+    v_threshold: i64,
+    v_rest: i64,
+    v_reset: i64,
+    tau: f64,
+    seed: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FixedLifState {
+    pub v_mem: i64,
+    // passi trascorsi dall'ultimo ingresso positivo
+    pub elapsed: f64,
+    rng: Rng,
+}
+
+impl FixedLif {
+    pub fn new(config: FixedPointConfig, v_threshold: f64, v_rest: f64, v_reset: f64, tau: f64, seed: u64) -> Self {
+        // i parametri di tensione vengono arrotondati al valore più vicino del formato del potenziale
+        let mut rng = Rng::new(seed);
+        let q = |x: f64, rng: &mut Rng| config.membrane.to_raw(x, Overflow::Saturate, Rounding::Nearest, rng);
+        Self {
+            config,
+            v_threshold: q(v_threshold, &mut rng),
+            v_rest: q(v_rest, &mut rng),
+            v_reset: q(v_reset, &mut rng),
+            tau,
+            seed,
+        }
+    }
+
+    fn membrane(&self, raw: i128) -> i64 {
+        self.config.membrane.overflow(raw, self.config.overflow)
+    }
+
+    fn quantize_input(&self, x: f64, rng: &mut Rng) -> i64 {
+        self.config.membrane.to_raw(x, self.config.overflow, self.config.rounding, rng)
+    }
+}
+
+impl NeuronModel for FixedLif {
+    type State = FixedLifState;
+
+    fn init_state(&self) -> FixedLifState {
+        FixedLifState { v_mem: self.v_rest, elapsed: 0.0, rng: Rng::new(self.seed) }
+    }
+
+    fn update(&self, state: &mut FixedLifState, inputs: &[f64], dt: f64, threshold_offset: f64) -> bool {
+        state.elapsed += dt;
+        if inputs.is_empty() {
+            return false;
+        }
+        let rng = &mut state.rng;
+        let decay = self.config.decay.to_raw(exp(-(state.elapsed / self.tau)), Overflow::Saturate, self.config.rounding, rng);
+        let delta = self.membrane(state.v_mem as i128 - self.v_rest as i128);
+        let decayed = self.membrane(shift_round(delta as i128 * decay as i128, self.config.decay.fraction_bits, self.config.rounding, rng));
+        let mut v_mem = self.membrane(self.v_rest as i128 + decayed as i128);
+        // gli ingressi (peso per spike, eventualmente modulato dalle sinapsi) vengono accumulati uno alla volta
+        for input in inputs {
+            let input = self.quantize_input(*input, rng);
+            v_mem = self.membrane(v_mem as i128 + input as i128);
+        }
+        state.v_mem = v_mem;
+        if inputs.iter().any(|x| *x > 0.0) {
+            state.elapsed = 0.0;
+        }
+        let offset = self.quantize_input(threshold_offset, rng);
+        state.v_mem as i128 > self.v_threshold as i128 + offset as i128
+    }
+
+    fn reset(&self, state: &mut FixedLifState) {
+        state.v_mem = self.v_reset;
+    }
+
+    fn v_mem(&self, state: &FixedLifState) -> f64 {
+        self.config.membrane.to_f64(state.v_mem)
+    }
+
+    fn state_variables(&self, state: &FixedLifState) -> Vec<(&'static str, f64)> {
+        vec![("v_mem", self.v_mem(state)), ("elapsed", state.elapsed)]
+    }
+
+    fn v_threshold(&self) -> f64 {
+        self.config.membrane.to_f64(self.v_threshold)
+    }
+
+    fn register<'a>(&'a mut self, _state: &'a mut FixedLifState, _component: FaultComponent) -> Option<&'a mut f64> {
+        // i valori sono interi del formato Q, i guasti IEEE-754 non si applicano
+        None
+    }
+
+    fn event_driven(&self) -> bool {
+        // come il lif, senza ingressi cambia solo elapsed
+        true
+    }
+}
+
+/*
+Errore di quantizzazione dei pesi di una matrice, calcolato sugli elementi memorizzati.
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QuantizationError {
+    pub name: String,
+    pub count: usize,
+    pub max_abs: f64,
+    pub mean_abs: f64,
+    pub rmse: f64,
+    // pesi fuori dall'intervallo del formato
+    pub overflowed: usize,
+    // pesi diversi da 0 diventati 0
+    pub zeroed: usize,
+}
+
+impl QuantizationError {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Self::default() }
+    }
+
+    pub fn add(&mut self, original: f64, quantized: f64, overflowed: bool) {
+        let error = (quantized - original).abs();
+        let n = self.count as f64;
+        self.max_abs = self.max_abs.max(error);
+        self.mean_abs = (self.mean_abs * n + error) / (n + 1.0);
+        self.rmse = ((self.rmse * self.rmse * n + error * error) / (n + 1.0)).sqrt();
+        self.count += 1;
+        self.overflowed += usize::from(overflowed);
+        self.zeroed += usize::from(original != 0.0 && quantized == 0.0);
+    }
+}
+
+impl fmt::Display for QuantizationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} weights, max error {:.3e}, mean error {:.3e}, rmse {:.3e}, {} overflowed, {} set to 0",
+            self.name, self.count, self.max_abs, self.mean_abs, self.rmse, self.overflowed, self.zeroed
+        )
+    }
+}
+
+pub fn quantize_weight(config: &FixedPointConfig, weight: f64, rng: &mut Rng, error: &mut QuantizationError) -> f64 {
+    // quantizza un peso nel formato weight, aggiornando l'errore della matrice
+    let limit = (1u64 << config.weight.integer_bits) as f64;
+    let overflowed = weight < -limit || weight > limit - config.weight.resolution();
+    let quantized = config.weight.quantize(weight, config.overflow, config.rounding, rng);
+    error.add(weight, quantized, overflowed);
+    quantized
+}
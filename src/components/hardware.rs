@@ -0,0 +1,228 @@
+use libm::exp;
+use serde::{Deserialize, Serialize};
+
+use super::errors::SNNError;
+use super::fault::{Fault, FaultComponent};
+use super::models::{Lif, LifState, NeuronModel};
+
+/*
+Unità funzionali e registri del datapath del neurone lif. Il potenziale viene aggiornato con la stessa sequenza di operazioni
+della funzione models::lif:
+  decay = exp(-(elapsed / tau))                      (decay_unit)
+  leaked = v_rest + (v_mem - v_rest) * decay          (leak_subtractor, decay_multiplier, rest_adder)
+  sum = -0 + w_1 + ... + w_n                          (weight_adder, un'addizione per ingresso)
+  v_mem = leaked + sum                                (membrane_adder, scritto in v_mem_register)
+  spike = v_mem > v_threshold + offset               (threshold_adder, comparator)
+e dopo una spike v_mem = reset_mux(spike, v_mem, v_reset).
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatapathUnit {
+    // registro dei passi trascorsi dall'ultimo ingresso positivo: input0 è il valore scritto, output quello letto
+    ElapsedRegister,
+    // input0 elapsed, output il fattore di decadimento
+    DecayUnit,
+    // input0 v_mem, input1 v_rest
+    LeakSubtractor,
+    // input0 v_mem - v_rest, input1 il fattore di decadimento
+    DecayMultiplier,
+    // input0 v_rest, input1 il potenziale decaduto rispetto al riposo
+    RestAdder,
+    // accumulatore degli ingressi pesati: input0 la somma parziale, input1 l'ingresso
+    WeightAdder,
+    // input0 il potenziale decaduto, input1 la somma degli ingressi
+    MembraneAdder,
+    // input0 v_threshold, input1 l'innalzamento della soglia deciso dal neurone
+    ThresholdAdder,
+    // input0 v_mem, input1 la soglia; l'uscita è un solo bit (bit 0)
+    Comparator,
+    // input0 la selezione (un bit, 1 dopo una spike), input1 v_mem, input2 v_reset
+    ResetMux,
+    // registro del potenziale di membrana: input0 è il valore scritto, output quello letto
+    VMemRegister,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatapathPort {
+    Input0,
+    Input1,
+    Input2,
+    Output,
+}
+
+impl DatapathUnit {
+    pub const ALL: [DatapathUnit; 11] = [
+        DatapathUnit::ElapsedRegister,
+        DatapathUnit::DecayUnit,
+        DatapathUnit::LeakSubtractor,
+        DatapathUnit::DecayMultiplier,
+        DatapathUnit::RestAdder,
+        DatapathUnit::WeightAdder,
+        DatapathUnit::MembraneAdder,
+        DatapathUnit::ThresholdAdder,
+        DatapathUnit::Comparator,
+        DatapathUnit::ResetMux,
+        DatapathUnit::VMemRegister,
+    ];
+
+    pub fn ports(self) -> &'static [DatapathPort] {
+        match self {
+            DatapathUnit::ElapsedRegister | DatapathUnit::DecayUnit | DatapathUnit::VMemRegister => &[DatapathPort::Input0, DatapathPort::Output],
+            DatapathUnit::ResetMux => &[DatapathPort::Input0, DatapathPort::Input1, DatapathPort::Input2, DatapathPort::Output],
+            _ => &[DatapathPort::Input0, DatapathPort::Input1, DatapathPort::Output],
+        }
+    }
+}
+
+pub fn datapath_components() -> Vec<FaultComponent> {
+    // tutti i segnali del datapath, ad esempio per estendere i componenti di una campagna di guasti
+    DatapathUnit::ALL
+        .iter()
+        .flat_map(|unit| unit.ports().iter().map(|port| FaultComponent::Datapath { unit: *unit, port: *port }))
+        .collect()
+}
+
+/*
+Guasti sui segnali del datapath di un neurone, con il passo corrente impostato dal neurone prima di ogni aggiornamento. Ogni
+valore che attraversa una porta viene intercettato: i guasti permanenti ne forzano i bit a ogni passo, i bit-flip solo nel
+passo indicato.
+*/
+#[derive(Clone, Debug, Default)]
+pub struct Datapath {
+    faults: Vec<Fault>,
+    tick: usize,
+}
+
+impl Datapath {
+    pub fn add_fault(&mut self, fault: Fault) -> Result<(), SNNError> {
+        match fault.component {
+            FaultComponent::Datapath { unit, port } if unit.ports().contains(&port) => {
+                self.faults.push(Fault { synapse: None, ..fault });
+                Ok(())
+            }
+            FaultComponent::Datapath { unit, port } => Err(SNNError::BadFormatError(format!("The {unit:?} unit has no {port:?} port"))),
+            component => Err(SNNError::BadFormatError(format!("{component:?} is not a datapath signal"))),
+        }
+    }
+
+    pub fn clear_faults(&mut self) {
+        self.faults.clear();
+    }
+
+    pub fn set_tick(&mut self, tick: usize) {
+        self.tick = tick;
+    }
+
+    fn tap_bits(&self, unit: DatapathUnit, port: DatapathPort, mut bits: u64) -> u64 {
+        for fault in &self.faults {
+            if fault.component == (FaultComponent::Datapath { unit, port }) && fault.is_active(self.tick) {
+                bits = fault.kind.apply_bits(bits, fault.bit);
+            }
+        }
+        bits
+    }
+
+    fn tap(&self, unit: DatapathUnit, port: DatapathPort, value: f64) -> f64 {
+        if self.faults.is_empty() {
+            return value;
+        }
+        f64::from_bits(self.tap_bits(unit, port, value.to_bits()))
+    }
+
+    fn tap_bit(&self, unit: DatapathUnit, port: DatapathPort, value: bool) -> bool {
+        self.tap_bits(unit, port, u64::from(value)) & 1 != 0
+    }
+
+    fn unit(&self, unit: DatapathUnit, a: f64, b: f64, op: impl Fn(f64, f64) -> f64) -> f64 {
+        // unità a due ingressi, con i guasti applicati agli ingressi e all'uscita
+        let a = self.tap(unit, DatapathPort::Input0, a);
+        let b = self.tap(unit, DatapathPort::Input1, b);
+        self.tap(unit, DatapathPort::Output, op(a, b))
+    }
+}
+
+/*
+Vista hardware del neurone lif: stessi parametri e stesso stato, ma l'aggiornamento attraversa le unità di Datapath. Come il
+lif, il datapath è attivo solo nei passi con almeno un ingresso (negli altri viene aggiornato solo elapsed_register); senza
+guasti il risultato coincide bit per bit con models::lif.
+*/
+#[derive(Clone, Debug)]
+pub struct HardwareLif {
+    pub lif: Lif,
+    pub datapath: Datapath,
+}
+
+impl HardwareLif {
+    pub fn new(lif: Lif) -> Self {
+        Self { lif, datapath: Datapath::default() }
+    }
+}
+
+impl NeuronModel for HardwareLif {
+    type State = LifState;
+
+    fn init_state(&self) -> LifState {
+        self.lif.init_state()
+    }
+
+    fn update(&self, state: &mut LifState, inputs: &[f64], dt: f64, threshold_offset: f64) -> bool {
+        use DatapathPort::*;
+        use DatapathUnit::*;
+        let dp = &self.datapath;
+        state.elapsed = dp.tap(ElapsedRegister, Input0, state.elapsed + dt);
+        if inputs.is_empty() {
+            return false;
+        }
+        let elapsed = dp.tap(ElapsedRegister, Output, state.elapsed);
+        let v_mem = dp.tap(VMemRegister, Output, state.v_mem);
+        let v_rest = self.lif.v_rest;
+
+        let decay = dp.tap(DecayUnit, Output, exp(-(dp.tap(DecayUnit, Input0, elapsed) / self.lif.tau)));
+        let delta = dp.unit(LeakSubtractor, v_mem, v_rest, |a, b| a - b);
+        let decayed = dp.unit(DecayMultiplier, delta, decay, |a, b| a * b);
+        let leaked = dp.unit(RestAdder, v_rest, decayed, |a, b| a + b);
+        // stessa somma di Iterator::sum per f64, che parte da -0
+        let sum = inputs.iter().fold(-0.0, |acc, w| dp.unit(WeightAdder, acc, *w, |a, b| a + b));
+        state.v_mem = dp.tap(VMemRegister, Input0, dp.unit(MembraneAdder, leaked, sum, |a, b| a + b));
+        if inputs.iter().any(|x| *x > 0.0) {
+            state.elapsed = dp.tap(ElapsedRegister, Input0, 0.0);
+        }
+
+        let threshold = dp.unit(ThresholdAdder, self.lif.v_threshold, threshold_offset, |a, b| a + b);
+        let a = dp.tap(Comparator, Input0, state.v_mem);
+        let b = dp.tap(Comparator, Input1, threshold);
+        dp.tap_bit(Comparator, Output, a > b)
+    }
+
+    fn reset(&self, state: &mut LifState) {
+        use DatapathPort::*;
+        use DatapathUnit::*;
+        let dp = &self.datapath;
+        let select = dp.tap_bit(ResetMux, Input0, true);
+        let held = dp.tap(ResetMux, Input1, state.v_mem);
+        let v_reset = dp.tap(ResetMux, Input2, self.lif.v_reset);
+        let out = dp.tap(ResetMux, Output, if select { v_reset } else { held });
+        state.v_mem = dp.tap(VMemRegister, Input0, out);
+    }
+
+    fn v_mem(&self, state: &LifState) -> f64 {
+        state.v_mem
+    }
+
+    fn state_variables(&self, state: &LifState) -> Vec<(&'static str, f64)> {
+        self.lif.state_variables(state)
+    }
+
+    fn v_threshold(&self) -> f64 {
+        self.lif.v_threshold
+    }
+
+    fn register<'a>(&'a mut self, state: &'a mut LifState, component: FaultComponent) -> Option<&'a mut f64> {
+        self.lif.register(state, component)
+    }
+
+    fn datapath(&mut self) -> Option<&mut Datapath> {
+        Some(&mut self.datapath)
+    }
+}
@@ -9,6 +9,7 @@ use super::spike::Spike;
 /*
 The input class contiains the logic to emit single spike to the first neural layer.
 */
+#[derive(Clone)]
 pub struct Input {
     // vettore di spike che invia: a ogni posizione corrisponde un ts e la cella corrisponde alla spike da inviare   
     spikes: Vec<i8>,
@@ -48,8 +49,13 @@ impl Input {
             barrier.wait();
         }
     }
+    pub fn spikes(&self) -> &[i8] {
+        // treno di spike emesso dall'input, una posizione per ts
+        &self.spikes
+    }
+
     pub fn is_empty_sender(&self) -> bool {
-        return self.senders.is_empty();
+        self.senders.is_empty()
     }
 
     pub fn add_sender(&mut self, tx: Sender<Spike>) {
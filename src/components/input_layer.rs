@@ -1,3 +1,4 @@
+use std::fmt;
 use std::fs::File;
 use std::io::Read;
 use std::num::ParseIntError;
@@ -6,6 +7,7 @@ use std::sync::{Barrier, Arc};
 use std::sync::mpsc::Sender;
 use std::thread::{JoinHandle, self};
 
+use super::encoder::Encoding;
 use super::errors::SNNError;
 use super::input::{Input};
 use super::spike::Spike;
@@ -13,6 +15,7 @@ use super::spike::Spike;
 /*
 Conenitore di oggetti Input
 */
+#[derive(Clone)]
 pub struct InputLayer {
     // vettore di input
     pub inputs: Vec<Input>,
@@ -24,11 +27,10 @@ impl InputLayer {
             // controlla che ci siano delle spike da emettere
             return Err(SNNError::EmptyInputLayer("Input layer is empty, please specify at least a file.".to_string()));
         }
-        for input in &self.inputs {
-            // controlla che ogni input sia connesso a almeno a un neurone
-            if input.is_empty_sender() {
-                return Err(SNNError::EmptyChannelError("Call the connect_inputs method of the neural network class before running the simulation.".to_string()))
-            }
+        // controlla che gli input siano connessi al primo layer: un input senza neuroni collegati (ad esempio con tutti i pesi
+        // potati in una matrice sparsa) emette comunque il suo treno, che non raggiunge nessuno
+        if self.inputs.iter().all(|input| input.is_empty_sender()) {
+            return Err(SNNError::EmptyChannelError("Call the connect_inputs method of the neural network class before running the simulation.".to_string()))
         }
         Ok(())
     }
@@ -68,7 +70,17 @@ impl InputLayer {
                 
             }
         }
-        return Ok(Self { inputs })
+        Ok(Self { inputs })
+    }
+
+    pub fn from_spikes(trains: Vec<Vec<i8>>) -> Self {
+        // crea un input per ogni treno di spike
+        Self { inputs: trains.into_iter().map(Input::new).collect() }
+    }
+
+    pub fn from_analog_file(path: &str, encoding: &Encoding) -> Result<Self, SNNError> {
+        // legge i valori analogici degli input (vedi Encoding::encode_file) e li codifica in treni di spike
+        Ok(Self::from_spikes(encoding.encode_file(path)?))
     }
 
     pub fn add_sender_to(&mut self, n_input: usize, tx: Sender<Spike>) {
@@ -76,14 +88,11 @@ impl InputLayer {
         self.inputs[n_input].add_sender(tx);
     }
 
-    pub fn emit_spikes(self) -> Vec<JoinHandle<()>> {
+    pub fn emit_spikes(self) -> Result<Vec<JoinHandle<()>>, SNNError> {
         // vector of thread ids belonging to each spike generator
         let mut tids = vec![];
-        // check the inputs status before proceding, modify for handling the result 
-        let res = self.check_inputs();
-        if res.is_err(){
-            panic!("{:?}", res)
-        }
+        // check the inputs status before proceding
+        self.check_inputs()?;
         // a ogni input corrisponde un thread
         let n_thread = self.inputs.len();
         let barrier = Arc::new(Barrier::new(n_thread));
@@ -97,11 +106,13 @@ impl InputLayer {
             });
             tids.push(child);
         }
-        return tids;
+        Ok(tids)
     }
 
-    pub fn to_string(&self) -> String {
-        let res = format!("input layer with [{}] inputs.\n", self.inputs.len());
-        res
+}
+
+impl fmt::Display for InputLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "input layer with [{}] inputs.", self.inputs.len())
     }
 }
@@ -0,0 +1,106 @@
+use libm::{exp, log};
+use serde::Deserialize;
+
+use super::synapse::Projection;
+
+/*
+Forma della risposta sinaptica a una singola spike. Senza cinetica la spike produce un salto istantaneo pari al peso, con un
+kernel il contributo viene distribuito sui passi successivi.
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Kernel {
+    // decadimento esponenziale con costante tau
+    Exponential { tau: f64 },
+    // differenza di esponenziali, normalizzata in modo che il picco sia pari al peso
+    DualExponential { tau_rise: f64, tau_decay: f64 },
+    // funzione alfa (t / tau) * exp(1 - t / tau), con picco pari al peso dopo tau
+    Alpha { tau: f64 },
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct ReversalPotentials {
+    // potenziali di inversione delle sinapsi eccitatorie (peso positivo) e inibitorie (peso negativo)
+    pub e_exc: f64,
+    pub e_inh: f64,
+}
+
+/*
+Cinetica delle sinapsi di una proiezione, letta dal campo "kinetics" del file JSON. Senza potenziali di inversione la sinapsi è
+a corrente (CUBA) e il kernel descrive direttamente la corrente; con i potenziali di inversione è a conduttanza (COBA): il
+kernel descrive la conduttanza g, proporzionale al modulo del peso, e la corrente è g * (E - v_mem).
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct KineticsConfig {
+    pub layer: usize,
+    pub projection: Projection,
+    pub kernel: Kernel,
+    pub reversal: Option<ReversalPotentials>,
+}
+
+impl KineticsConfig {
+    pub fn is_valid(&self) -> bool {
+        match self.kernel {
+            Kernel::Exponential { tau } | Kernel::Alpha { tau } => tau > 0.0,
+            Kernel::DualExponential { tau_rise, tau_decay } => tau_rise > 0.0 && tau_rise < tau_decay,
+        }
+    }
+}
+
+/*
+Stato della cinetica di una singola sinapsi, due variabili sono sufficienti per tutti i kernel.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Kinetics {
+    config: KineticsConfig,
+    x: f64,
+    y: f64,
+}
+
+impl Kinetics {
+    pub fn new(config: KineticsConfig) -> Self {
+        Self { config, x: 0.0, y: 0.0 }
+    }
+
+    pub fn reset(&mut self) {
+        self.x = 0.0;
+        self.y = 0.0;
+    }
+
+    fn step(&mut self, input: f64, dt: f64) -> f64 {
+        // avanza il kernel di un passo lungo dt con l'ingresso istantaneo ricevuto, restituisce il valore del kernel
+        match self.config.kernel {
+            Kernel::Exponential { tau } => {
+                self.x = self.x * exp(-dt / tau) + input;
+                self.x
+            }
+            Kernel::DualExponential { tau_rise, tau_decay } => {
+                // fattore di normalizzazione calcolato sull'istante del picco
+                let t_peak = tau_rise * tau_decay / (tau_decay - tau_rise) * log(tau_decay / tau_rise);
+                let norm = 1.0 / (exp(-t_peak / tau_decay) - exp(-t_peak / tau_rise));
+                self.x = self.x * exp(-dt / tau_decay) + input * norm;
+                self.y = self.y * exp(-dt / tau_rise) + input * norm;
+                self.x - self.y
+            }
+            Kernel::Alpha { tau } => {
+                // x decade esponenzialmente e alimenta y, che segue la funzione alfa
+                let decay = exp(-dt / tau);
+                self.y = decay * (self.y + dt / tau * exp(1.0) * self.x);
+                self.x = self.x * decay + input;
+                self.y
+            }
+        }
+    }
+
+    pub fn current(&mut self, input: f64, weight: f64, v_mem: f64, dt: f64) -> f64 {
+        // corrente sinaptica del passo corrente dato l'ingresso istantaneo (spike * peso)
+        match self.config.reversal {
+            None => self.step(input, dt),
+            Some(reversal) => {
+                let g = self.step(input.abs(), dt);
+                let e = if weight < 0.0 { reversal.e_inh } else { reversal.e_exc };
+                g * (e - v_mem)
+            }
+        }
+    }
+}
@@ -7,4 +7,26 @@ pub mod output;
 pub mod synapse;
 pub mod spike;
 pub mod errors;
-pub mod models;
\ No newline at end of file
+pub mod models;
+pub mod refractory;
+pub mod threshold;
+pub mod stdp;
+pub mod stp;
+pub mod kinetics;
+pub mod engine;
+pub mod threaded_engine;
+pub mod clock_engine;
+pub mod event_engine;
+pub mod parallel_engine;
+pub mod sparse;
+pub mod dataset;
+pub mod evaluation;
+pub mod raster;
+pub mod probe;
+pub mod rng;
+pub mod encoder;
+pub mod fault;
+pub mod campaign;
+pub mod hardware;
+pub mod fixed;
+pub mod compression;
@@ -1,12 +1,491 @@
 use libm::exp;
+use serde::Deserialize;
 
-pub fn lif(ts: i32, ts_1: i32, v_rest: f64, v_mem_old: f64, tao: f64, weights: Vec<f64>) -> f64 {
-    let k = -((ts - ts_1) as f64 / tao);
+use super::fault::FaultComponent;
+use super::hardware::{Datapath, HardwareLif};
+
+/*
+Interfaccia comune dei modelli di neurone. Il modello contiene solo i parametri, mentre lo stato (potenziale di membrana ed
+eventuali variabili ausiliarie) è un tipo associato che ogni neurone conserva e passa al modello a ogni passo di simulazione.
+*/
+pub trait NeuronModel: Send + 'static {
+    // stato per-neurone del modello
+    type State: Clone + Send + 'static;
+
+    // stato iniziale del neurone, a riposo
+    fn init_state(&self) -> Self::State;
+
+    // integra gli ingressi pesati ricevuti in un passo lungo dt, restituisce true se il neurone scatta.
+    // threshold_offset è l'innalzamento della soglia deciso dal neurone (es. periodo refrattario relativo), 0 se assente
+    fn update(&self, state: &mut Self::State, inputs: &[f64], dt: f64, threshold_offset: f64) -> bool;
+
+    // riporta lo stato ai valori successivi a una spike
+    fn reset(&self, state: &mut Self::State);
+
+    // potenziale di membrana corrente
+    fn v_mem(&self, state: &Self::State) -> f64;
+
+    // soglia di base, a cui si somma threshold_offset
+    fn v_threshold(&self) -> f64;
+
+    // true se senza ingressi lo stato dipende solo dal tempo trascorso, cioè una update senza ingressi lunga n passi equivale a
+    // n update lunghe un passo: in questo caso il motore event-driven può saltare gli intervalli silenziosi
+    fn event_driven(&self) -> bool {
+        false
+    }
+
+    // variabili di stato osservabili dalle sonde, come (nome, valore)
+    fn state_variables(&self, state: &Self::State) -> Vec<(&'static str, f64)> {
+        vec![("v_mem", self.v_mem(state))]
+    }
+
+    // parametro o variabile di stato colpiti da un guasto, None se il modello non ha il componente
+    fn register<'a>(&'a mut self, state: &'a mut Self::State, component: FaultComponent) -> Option<&'a mut f64>;
+
+    // datapath con i guasti sui segnali interni, None se il modello non ha una vista hardware
+    fn datapath(&mut self) -> Option<&mut Datapath> {
+        None
+    }
+}
+
+/*
+Versione object-safe di NeuronModel: unisce un modello al suo stato in modo che i neuroni di uno stesso layer (o di layer
+diversi) possano usare modelli diversi attraverso un Box<dyn NeuronDynamics>.
+*/
+pub trait NeuronDynamics: Send {
+    fn update(&mut self, inputs: &[f64], dt: f64, threshold_offset: f64) -> bool;
+    fn reset(&mut self);
+    fn v_mem(&self) -> f64;
+    fn v_threshold(&self) -> f64;
+    fn event_driven(&self) -> bool;
+    // riporta lo stato a quello iniziale
+    fn reset_state(&mut self);
+    fn state_variables(&self) -> Vec<(&'static str, f64)>;
+    fn register(&mut self, component: FaultComponent) -> Option<&mut f64>;
+    fn datapath(&mut self) -> Option<&mut Datapath>;
+}
+
+pub struct ModelInstance<M: NeuronModel> {
+    model: M,
+    state: M::State,
+}
+
+impl<M: NeuronModel> ModelInstance<M> {
+    pub fn new(model: M) -> Self {
+        let state = model.init_state();
+        Self { model, state }
+    }
+}
+
+impl<M: NeuronModel> NeuronDynamics for ModelInstance<M> {
+    fn update(&mut self, inputs: &[f64], dt: f64, threshold_offset: f64) -> bool {
+        self.model.update(&mut self.state, inputs, dt, threshold_offset)
+    }
+
+    fn reset(&mut self) {
+        self.model.reset(&mut self.state)
+    }
+
+    fn v_mem(&self) -> f64 {
+        self.model.v_mem(&self.state)
+    }
+
+    fn v_threshold(&self) -> f64 {
+        self.model.v_threshold()
+    }
+
+    fn event_driven(&self) -> bool {
+        self.model.event_driven()
+    }
+
+    fn reset_state(&mut self) {
+        self.state = self.model.init_state();
+    }
+
+    fn state_variables(&self) -> Vec<(&'static str, f64)> {
+        self.model.state_variables(&self.state)
+    }
+
+    fn register(&mut self, component: FaultComponent) -> Option<&mut f64> {
+        self.model.register(&mut self.state, component)
+    }
+
+    fn datapath(&mut self) -> Option<&mut Datapath> {
+        self.model.datapath()
+    }
+}
+
+pub fn boxed<M: NeuronModel>(model: M) -> Box<dyn NeuronDynamics> {
+    Box::new(ModelInstance::new(model))
+}
+
+impl<M: NeuronModel> From<M> for Box<dyn NeuronDynamics> {
+    fn from(model: M) -> Self {
+        boxed(model)
+    }
+}
+
+// decadimento esponenziale verso v_rest dopo dt passi, sommato agli ingressi pesati
+pub fn lif(dt: f64, v_rest: f64, v_mem_old: f64, tao: f64, weights: &[f64]) -> f64 {
+    let k = -(dt / tao);
 
     let exponential = exp(k);
 
     let v_mem = v_rest + (v_mem_old - v_rest) * exponential;
 
     let weight = weights.iter().sum::<f64>();
-    return v_mem + weight;
+    v_mem + weight
+}
+
+/*
+Leaky integrate and fire: il potenziale viene aggiornato solo quando arriva almeno un ingresso, il decadimento è calcolato in
+forma chiusa sul numero di passi trascorsi dall'ultimo ingresso eccitatorio.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Lif {
+    pub v_threshold: f64,
+    pub v_rest: f64,
+    pub v_reset: f64,
+    pub tau: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LifState {
+    pub v_mem: f64,
+    // passi trascorsi dall'ultimo ingresso positivo
+    pub elapsed: f64,
+}
+
+impl Lif {
+    pub fn new(v_threshold: f64, v_rest: f64, v_reset: f64, tau: f64) -> Self {
+        Self { v_threshold, v_rest, v_reset, tau }
+    }
+}
+
+impl NeuronModel for Lif {
+    type State = LifState;
+
+    fn init_state(&self) -> LifState {
+        // all'inizio la rete è a riposo
+        LifState { v_mem: self.v_rest, elapsed: 0.0 }
+    }
+
+    fn update(&self, state: &mut LifState, inputs: &[f64], dt: f64, threshold_offset: f64) -> bool {
+        state.elapsed += dt;
+        if inputs.is_empty() {
+            return false;
+        }
+        state.v_mem = lif(state.elapsed, self.v_rest, state.v_mem, self.tau, inputs);
+        if inputs.iter().any(|x| *x > 0.0) {
+            state.elapsed = 0.0;
+        }
+        state.v_mem > self.v_threshold + threshold_offset
+    }
+
+    fn reset(&self, state: &mut LifState) {
+        state.v_mem = self.v_reset;
+    }
+
+    fn v_mem(&self, state: &LifState) -> f64 {
+        state.v_mem
+    }
+
+    fn state_variables(&self, state: &LifState) -> Vec<(&'static str, f64)> {
+        // il potenziale memorizzato è quello dell'ultimo ingresso, la sonda osserva quello decaduto fino al passo corrente
+        vec![("v_mem", lif(state.elapsed, self.v_rest, state.v_mem, self.tau, &[])), ("elapsed", state.elapsed)]
+    }
+
+    fn v_threshold(&self) -> f64 {
+        self.v_threshold
+    }
+
+    fn register<'a>(&'a mut self, state: &'a mut LifState, component: FaultComponent) -> Option<&'a mut f64> {
+        match component {
+            FaultComponent::VThreshold => Some(&mut self.v_threshold),
+            FaultComponent::VRest => Some(&mut self.v_rest),
+            FaultComponent::VReset => Some(&mut self.v_reset),
+            FaultComponent::VMem => Some(&mut state.v_mem),
+            FaultComponent::Weight | FaultComponent::Datapath { .. } => None,
+        }
+    }
+
+    fn event_driven(&self) -> bool {
+        // il decadimento viene calcolato in forma chiusa su elapsed al prossimo ingresso
+        true
+    }
+}
+
+/*
+Modello di Izhikevich (2003): potenziale di membrana v e variabile di recupero u, con parametri a/b/c/d. Gli ingressi pesati
+ricevuti in un passo vengono sommati e trattati come corrente I.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Izhikevich {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    // picco del potenziale oltre il quale il neurone scatta
+    pub v_peak: f64,
+    // potenziale iniziale
+    pub v_init: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IzhikevichPreset {
+    RegularSpiking,
+    FastSpiking,
+    IntrinsicallyBursting,
+    Chattering,
+    LowThreshold,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct IzhikevichState {
+    pub v: f64,
+    pub u: f64,
+}
+
+impl Izhikevich {
+    pub fn new(a: f64, b: f64, c: f64, d: f64) -> Self {
+        Self { a, b, c, d, v_peak: 30.0, v_init: -65.0 }
+    }
+
+    pub fn from_preset(preset: IzhikevichPreset) -> Self {
+        // parametri della tabella in "Simple Model of Spiking Neurons", Izhikevich 2003
+        match preset {
+            IzhikevichPreset::RegularSpiking => Self::new(0.02, 0.2, -65.0, 8.0),
+            IzhikevichPreset::FastSpiking => Self::new(0.1, 0.2, -65.0, 2.0),
+            IzhikevichPreset::IntrinsicallyBursting => Self::new(0.02, 0.2, -55.0, 4.0),
+            IzhikevichPreset::Chattering => Self::new(0.02, 0.2, -50.0, 2.0),
+            IzhikevichPreset::LowThreshold => Self::new(0.02, 0.25, -65.0, 2.0),
+        }
+    }
+}
+
+impl NeuronModel for Izhikevich {
+    type State = IzhikevichState;
+
+    fn init_state(&self) -> IzhikevichState {
+        IzhikevichState { v: self.v_init, u: self.b * self.v_init }
+    }
+
+    fn update(&self, state: &mut IzhikevichState, inputs: &[f64], dt: f64, threshold_offset: f64) -> bool {
+        let i = inputs.iter().sum::<f64>();
+        // v viene integrato in due mezzi passi per stabilità numerica, come nell'implementazione di riferimento
+        for _ in 0..2 {
+            state.v += 0.5 * dt * (0.04 * state.v * state.v + 5.0 * state.v + 140.0 - state.u + i);
+        }
+        state.u += dt * self.a * (self.b * state.v - state.u);
+        // il modello non ha una soglia esplicita, l'offset sposta il picco
+        let v_peak = self.v_peak + threshold_offset;
+        if state.v >= v_peak {
+            state.v = v_peak;
+            return true;
+        }
+        false
+    }
+
+    fn reset(&self, state: &mut IzhikevichState) {
+        state.v = self.c;
+        state.u += self.d;
+    }
+
+    fn v_mem(&self, state: &IzhikevichState) -> f64 {
+        state.v
+    }
+
+    fn state_variables(&self, state: &IzhikevichState) -> Vec<(&'static str, f64)> {
+        vec![("v_mem", state.v), ("u", state.u)]
+    }
+
+    fn v_threshold(&self) -> f64 {
+        self.v_peak
+    }
+
+    fn register<'a>(&'a mut self, state: &'a mut IzhikevichState, component: FaultComponent) -> Option<&'a mut f64> {
+        match component {
+            FaultComponent::VThreshold => Some(&mut self.v_peak),
+            FaultComponent::VRest => Some(&mut self.v_init),
+            FaultComponent::VReset => Some(&mut self.c),
+            FaultComponent::VMem => Some(&mut state.v),
+            FaultComponent::Weight | FaultComponent::Datapath { .. } => None,
+        }
+    }
+}
+
+/*
+Adaptive exponential integrate and fire (Brette & Gerstner 2005): all'integrazione lineare si aggiunge un termine esponenziale
+di innesco della spike e una corrente di adattamento w, accoppiata al potenziale sotto soglia (a) e incrementata di b a ogni
+spike. Unità: mV, ms, pF, nS e pA; gli ingressi pesati di un passo sono sommati e trattati come corrente in pA.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct AdEx {
+    // capacità di membrana
+    pub c: f64,
+    // conduttanza di leak
+    pub g_l: f64,
+    // potenziale di leak (riposo)
+    pub e_l: f64,
+    // soglia di innesco e pendenza del termine esponenziale
+    pub v_t: f64,
+    pub delta_t: f64,
+    // costante di tempo dell'adattamento, accoppiamento sotto soglia e incremento per spike
+    pub tau_w: f64,
+    pub a: f64,
+    pub b: f64,
+    pub v_reset: f64,
+    pub v_peak: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AdExState {
+    pub v: f64,
+    pub w: f64,
+}
+
+impl AdEx {
+    pub fn new(e_l: f64, v_t: f64, v_reset: f64) -> Self {
+        // parametri di default dell'articolo originale, tranne potenziali di riposo, soglia e reset
+        let delta_t = 2.0;
+        Self {
+            c: 281.0,
+            g_l: 30.0,
+            e_l,
+            v_t,
+            delta_t,
+            tau_w: 144.0,
+            a: 4.0,
+            b: 80.5,
+            v_reset,
+            v_peak: v_t + 5.0 * delta_t,
+        }
+    }
+}
+
+impl NeuronModel for AdEx {
+    type State = AdExState;
+
+    fn init_state(&self) -> AdExState {
+        AdExState { v: self.e_l, w: 0.0 }
+    }
+
+    fn update(&self, state: &mut AdExState, inputs: &[f64], dt: f64, threshold_offset: f64) -> bool {
+        let i = inputs.iter().sum::<f64>();
+        let v = state.v;
+        // l'offset sposta sia la soglia di innesco sia il picco
+        let v_t = self.v_t + threshold_offset;
+        let v_peak = self.v_peak + threshold_offset;
+        let spike_current = self.g_l * self.delta_t * exp((v - v_t) / self.delta_t);
+        let dv = (-self.g_l * (v - self.e_l) + spike_current - state.w + i) / self.c;
+        let dw = (self.a * (v - self.e_l) - state.w) / self.tau_w;
+        state.v += dt * dv;
+        state.w += dt * dw;
+        if state.v >= v_peak {
+            state.v = v_peak;
+            return true;
+        }
+        false
+    }
+
+    fn reset(&self, state: &mut AdExState) {
+        state.v = self.v_reset;
+        state.w += self.b;
+    }
+
+    fn v_mem(&self, state: &AdExState) -> f64 {
+        state.v
+    }
+
+    fn state_variables(&self, state: &AdExState) -> Vec<(&'static str, f64)> {
+        vec![("v_mem", state.v), ("w", state.w)]
+    }
+
+    fn v_threshold(&self) -> f64 {
+        self.v_t
+    }
+
+    fn register<'a>(&'a mut self, state: &'a mut AdExState, component: FaultComponent) -> Option<&'a mut f64> {
+        match component {
+            FaultComponent::VThreshold => Some(&mut self.v_t),
+            FaultComponent::VRest => Some(&mut self.e_l),
+            FaultComponent::VReset => Some(&mut self.v_reset),
+            FaultComponent::VMem => Some(&mut state.v),
+            FaultComponent::Weight | FaultComponent::Datapath { .. } => None,
+        }
+    }
+}
+
+/*
+Descrizione del modello letta dal file JSON della rete, nel campo "model" (per tutta la rete) o "layer_models" (uno per layer).
+In assenza di entrambi si usa il modello lif con i parametri globali rest_potential, reset_potential e tau.
+*/
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ModelConfig {
+    #[default]
+    Lif,
+    // lif con la vista hardware del datapath, per iniettare guasti nelle unità funzionali
+    HardwareLif,
+    Izhikevich {
+        preset: Option<IzhikevichPreset>,
+        // i singoli parametri sovrascrivono quelli del preset (regular spiking se non specificato)
+        a: Option<f64>,
+        b: Option<f64>,
+        c: Option<f64>,
+        d: Option<f64>,
+        v_peak: Option<f64>,
+    },
+    // i parametri non specificati assumono i valori di AdEx::new, con soglia v_t pari alla soglia del neurone
+    Adex {
+        c: Option<f64>,
+        g_l: Option<f64>,
+        v_t: Option<f64>,
+        delta_t: Option<f64>,
+        tau_w: Option<f64>,
+        a: Option<f64>,
+        b: Option<f64>,
+        v_peak: Option<f64>,
+    },
+}
+
+impl ModelConfig {
+    pub fn reads_threshold(&self) -> bool {
+        // true se la soglia del modello è quella del neurone nel campo thresholds: l'izhikevich usa v_peak e l'adex con v_t
+        // esplicita ignora thresholds
+        match self {
+            ModelConfig::Lif | ModelConfig::HardwareLif => true,
+            ModelConfig::Izhikevich { .. } => false,
+            ModelConfig::Adex { v_t, .. } => v_t.is_none(),
+        }
+    }
+
+    pub fn build(&self, v_threshold: f64, v_rest: f64, v_reset: f64, tau: f64) -> Box<dyn NeuronDynamics> {
+        match self {
+            ModelConfig::Lif => boxed(Lif::new(v_threshold, v_rest, v_reset, tau)),
+            ModelConfig::HardwareLif => boxed(HardwareLif::new(Lif::new(v_threshold, v_rest, v_reset, tau))),
+            ModelConfig::Izhikevich { preset, a, b, c, d, v_peak } => {
+                let mut m = Izhikevich::from_preset(preset.unwrap_or(IzhikevichPreset::RegularSpiking));
+                m.a = a.unwrap_or(m.a);
+                m.b = b.unwrap_or(m.b);
+                m.c = c.unwrap_or(m.c);
+                m.d = d.unwrap_or(m.d);
+                m.v_peak = v_peak.unwrap_or(m.v_peak);
+                m.v_init = v_rest;
+                boxed(m)
+            }
+            ModelConfig::Adex { c, g_l, v_t, delta_t, tau_w, a, b, v_peak } => {
+                let mut m = AdEx::new(v_rest, v_t.unwrap_or(v_threshold), v_reset);
+                m.c = c.unwrap_or(m.c);
+                m.g_l = g_l.unwrap_or(m.g_l);
+                m.delta_t = delta_t.unwrap_or(m.delta_t);
+                m.tau_w = tau_w.unwrap_or(m.tau_w);
+                m.a = a.unwrap_or(m.a);
+                m.b = b.unwrap_or(m.b);
+                m.v_peak = v_peak.unwrap_or(m.v_t + 5.0 * m.delta_t);
+                boxed(m)
+            }
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use std::{
+    panic::{self, AssertUnwindSafe},
     sync::{Arc, Barrier},
     thread::{self, JoinHandle},
 };
@@ -7,7 +8,7 @@ use std::sync::mpsc::{Receiver, Sender};
 
 use crate::components::neuron::Neuron;
 
-use super::{synapse::Synapse, spike::Spike, errors::SNNError};
+use super::{synapse::{Projection, Synapse}, spike::Spike, errors::SNNError};
 /*
 Struttura contenitore di Neuroni
 */
@@ -31,29 +32,31 @@ impl NeuralLayer {
         self.neurons.push(neuron);
     }
 
-    pub fn run_neurons(self) -> Vec<JoinHandle<()>> {
-        // lancia n_neurons thread attraverso il metodo run() dei singoli neuroni
-        // TODO: gestire gli errori
+    pub fn run_neurons(&mut self, receivers: Vec<Vec<Receiver<Spike>>>, outputs: Vec<Vec<Sender<Spike>>>) -> Vec<JoinHandle<(Neuron, Result<(), SNNError>)>> {
+        // lancia n_neurons thread attraverso il metodo run() dei singoli neuroni, ogni thread restituisce il proprio neurone
+        // al termine della simulazione in modo da poterne leggere lo stato finale, insieme all'esito della simulazione.
+        // receivers e outputs contengono, per ogni neurone, le estremità dei canali in ingresso (una per sinapsi) e in uscita
         let mut tids = vec![];
-        for mut neuron in self.neurons {
+        for (mut neuron, (receivers, outputs)) in std::mem::take(&mut self.neurons).into_iter().zip(receivers.into_iter().zip(outputs)) {
             // clone della barrier per condividerla con i thread da sincronizzare
             let barrier = Arc::clone(&self.barrier);
 
             let tid = thread::spawn(move || {
-                let res = neuron.run(barrier);
-                if res.is_err(){
-                    panic!("[Neural Layer]: {:?}", res)
-                }
+                // alla fine di run i sender vengono distrutti e i canali in uscita chiusi, altrimenti i neuroni collegati
+                // resterebbero in attesa di altre spike. Un panic viene catturato per restituire comunque il neurone al layer
+                let res = panic::catch_unwind(AssertUnwindSafe(|| neuron.run(barrier, receivers, outputs)));
+                let res = res.unwrap_or_else(|payload| Err(SNNError::from_panic("neuron", payload)));
+                (neuron, res)
             });
             tids.push(tid);
         }
 
-        return tids;
+        tids
     }
 
-    pub fn add_synapse(&mut self, neuron: usize, weight: f64, channel: Receiver<Spike>)  -> Result<(), SNNError>{
-        // aggiunge una sinapsi ricevendo peso e receiver a un neurone, return di result se neuron è out of bounds
-        let s = Synapse::new(weight, channel);
+    pub fn add_synapse(&mut self, neuron: usize, weight: f64, projection: Projection, source: usize, delay: u32)  -> Result<(), SNNError>{
+        // aggiunge una sinapsi ricevendo il peso a un neurone, return di result se neuron è out of bounds
+        let s = Synapse::new(weight, projection, source, delay);
         let len = self.neurons.len();
         if neuron >= self.neurons.len(){
             return Err(SNNError::OutOfIndexError(format!("Trying to add synapses to neuron [{neuron}] but there are only {len} in the layer")));
@@ -61,9 +64,4 @@ impl NeuralLayer {
         self.neurons[neuron].synapses.push(s);
         Ok(())
     }
-
-    pub fn add_sender(&mut self, neuron: usize, channel: Sender<Spike>) {
-        // aggiunge un sender al neuron-esimo neurone, restituire un error se è out of bounds 
-        self.neurons[neuron].output.push(channel);
-    }
 }
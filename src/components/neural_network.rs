@@ -1,10 +1,28 @@
-use std::{vec, sync::mpsc::channel};
+use std::{fmt, vec};
+use std::collections::BTreeMap;
 
 use std::fs::File;
 use serde::Deserialize;
 use std::io::Write;
 
-use super::{input_layer::InputLayer, neural_layer::NeuralLayer, neuron::Neuron, output::OutputMonitor, spike::Spike, errors::SNNError};
+use super::models::{boxed, ModelConfig, NeuronDynamics};
+use super::refractory::RefractoryConfig;
+use super::threshold::AdaptiveThresholdConfig;
+use super::stdp::StdpConfig;
+use super::stp::StpConfig;
+use super::kinetics::KineticsConfig;
+use super::synapse::Projection;
+use super::sparse::{Csr, MatrixSpec};
+use super::encoder::Encoding;
+use super::fault::{Fault, FaultComponent};
+use super::fixed::{quantize_weight, FixedLif, FixedPointConfig, QuantizationError};
+use super::rng::Rng;
+use super::dataset::Sample;
+use super::raster::{RasterTarget, SpikeRaster};
+use super::probe::StateProbe;
+use super::engine::Engine;
+use super::{clock_engine, event_engine, parallel_engine, threaded_engine};
+use super::{input_layer::InputLayer, neural_layer::NeuralLayer, neuron::Neuron, output::{OutputMonitor, OutputRecord}, errors::SNNError};
 
 #[derive(Debug, Deserialize)]
 struct Value {
@@ -12,9 +30,40 @@ struct Value {
     rest_potential: f64,
     reset_potential: f64,
     tau: f64,
-    intra_layer_weights: Vec<Vec<Vec<f64>>>,
-    input_weights: Vec<Vec<Vec<f64>>>,
+    // matrici dense o sparse (vedi MatrixSpec)
+    intra_layer_weights: Vec<MatrixSpec<f64>>,
+    input_weights: Vec<MatrixSpec<f64>>,
+    // ritardi in passi di simulazione, stessa forma delle matrici dei pesi (1 se assenti)
+    intra_layer_delays: Option<Vec<MatrixSpec<u32>>>,
+    input_delays: Option<Vec<MatrixSpec<u32>>>,
     inputs: String,
+    // codifica degli ingressi, se presente il file inputs contiene valori analogici (vedi Encoding::encode_file) invece delle spike
+    encoder: Option<Encoding>,
+    // modello dei neuroni, lif se assente
+    #[serde(default)]
+    model: ModelConfig,
+    // modello di ogni layer, sostituisce model
+    layer_models: Option<Vec<ModelConfig>>,
+    // periodo refrattario di ogni layer, nessuno se assente
+    #[serde(default)]
+    refractory: Vec<RefractoryConfig>,
+    // soglia adattiva di ogni layer, null per i layer con soglia fissa
+    #[serde(default)]
+    adaptive_threshold: Vec<Option<AdaptiveThresholdConfig>>,
+    // regole STDP delle proiezioni che apprendono
+    #[serde(default)]
+    stdp: Vec<StdpConfig>,
+    // plasticità a breve termine dei blocchi di connessioni
+    #[serde(default)]
+    stp: Vec<StpConfig>,
+    // cinetica delle sinapsi (CUBA o COBA) per proiezione
+    #[serde(default)]
+    kinetics: Vec<KineticsConfig>,
+    // guasti da iniettare durante la simulazione
+    #[serde(default)]
+    faults: Vec<Fault>,
+    // simulazione in virgola fissa dei layer lif, con i pesi quantizzati al caricamento
+    fixed_point: Option<FixedPointConfig>,
     }
 
 /*
@@ -27,136 +76,521 @@ pub struct NeuralNetwork {
     neural_layers: Vec<NeuralLayer>,
     // Option perchè si aggiunge dopo la new
     output_monitor: Option<OutputMonitor>,
+    // motore usato da run, threaded se non specificato
+    engine: Engine,
+    // raster collegati all'input layer o ai layer neurali
+    rasters: Vec<SpikeRaster>,
+    // errore di quantizzazione di ogni matrice dei pesi, vuoto se la rete non è in virgola fissa
+    quantization: Vec<QuantizationError>,
 }
 
 impl NeuralNetwork {
-    pub fn new(
+    pub fn new<D, F>(thresholds: Vec<Vec<f64>>, refractory: &[RefractoryConfig], model: F) -> Self
+    where
+        // un NeuronModel qualsiasi oppure un Box<dyn NeuronDynamics> già costruito
+        D: Into<Box<dyn NeuronDynamics>>,
+        // costruisce il modello di un neurone a partire dall'indice del layer e dalla soglia del neurone
+        F: Fn(usize, f64) -> D,
+    {
         // costruttore
-        v_rest: f64,
-        v_reset: f64,
-        tao: f64,
-        model: fn(i32, i32, f64, f64, f64, Vec<f64>) -> f64,
-        thresholds: Vec<Vec<f64>>,
-    ) -> Self {
+
+        // refractory contiene un elemento per layer, i layer mancanti non hanno periodo refrattario
+
         // è il vettore temporaneo dei Neural Layer
         let mut layers = vec![];
         // la prima dimensione di thresholds contiene il numero di layer
-        for layer in thresholds {
-            let mut nl = NeuralLayer::new(layer.len() as usize);
-            let mut n_neuron: i32 = 0;
-            for threshold in layer {
-                nl.add_neuron(Neuron::new(
-                    threshold,
-                    v_rest,
-                    v_reset,
-                    tao,
-                    model,
-                    n_neuron
-                ));
-                n_neuron+=1;
+        for (n_layer, layer) in thresholds.into_iter().enumerate() {
+            let mut nl = NeuralLayer::new(layer.len());
+            let layer_refractory = refractory.get(n_layer).copied().unwrap_or_default();
+            for (n_neuron, threshold) in layer.into_iter().enumerate() {
+                nl.add_neuron(Neuron::new(model(n_layer, threshold), layer_refractory, n_neuron as i32));
             }
             layers.push(nl);
-        } 
+        }
 
         Self {
             input_layer: None,
             neural_layers: layers,
             output_monitor: None,
+            engine: Engine::default(),
+            rasters: vec![],
+            quantization: vec![],
         }
     }
 
 
-    pub fn from_json(path: &str, model: fn(i32, i32, f64, f64, f64, Vec<f64>) -> f64)-> Result<NeuralNetwork, SNNError>{
+    pub fn from_json(path: &str) -> Result<NeuralNetwork, SNNError>{
         let file = File::open(path).unwrap();
-        let parameters: Value = serde_json::from_reader(file).expect("JSON was not well-formatted");
+        // campi non validi (es. un preset sconosciuto) vengono segnalati come errore di formato
+        let parameters: Value = match serde_json::from_reader(file) {
+            Ok(parameters) => parameters,
+            Err(e) => return Err(SNNError::BadFormatError(format!("File :{path}\nERROR:{e}"))),
+        };
         
         let last_layer_len = parameters.thresholds.last().unwrap().len();
         
-        let mut nn = NeuralNetwork::new(parameters.rest_potential, parameters.reset_potential, parameters.tau, model, parameters.thresholds);        
+        let (v_rest, v_reset, tau) = (parameters.rest_potential, parameters.reset_potential, parameters.tau);
+        let models = match parameters.layer_models {
+            None => vec![parameters.model; parameters.thresholds.len()],
+            Some(models) if models.len() == parameters.thresholds.len() => models,
+            Some(models) => return Err(SNNError::BadFormatError(format!(
+                "layer_models has {} entries but the network has {} layers", models.len(), parameters.thresholds.len()
+            ))),
+        };
+        if !parameters.refractory.is_empty() && parameters.refractory.len() != parameters.thresholds.len() {
+            return Err(SNNError::BadFormatError(format!(
+                "refractory has {} entries but the network has {} layers", parameters.refractory.len(), parameters.thresholds.len()
+            )));
+        }
+        if !parameters.adaptive_threshold.is_empty() && parameters.adaptive_threshold.len() != parameters.thresholds.len() {
+            return Err(SNNError::BadFormatError(format!(
+                "adaptive_threshold has {} entries but the network has {} layers", parameters.adaptive_threshold.len(), parameters.thresholds.len()
+            )));
+        }
+        if let Some(config) = parameters.fixed_point {
+            config.check()?;
+            if models.iter().any(|m| !matches!(m, ModelConfig::Lif)) {
+                return Err(SNNError::BadFormatError("The fixed point mode supports only lif layers".to_string()));
+            }
+        }
+        // indice globale del neurone, per dare a ognuno una sequenza diversa di arrotondamenti stocastici
+        let n_built = std::cell::Cell::new(0);
+        let mut nn = NeuralNetwork::new(parameters.thresholds, &parameters.refractory, |n_layer, threshold| {
+            match parameters.fixed_point {
+                None => models[n_layer].build(threshold, v_rest, v_reset, tau),
+                Some(config) => {
+                    n_built.set(n_built.get() + 1);
+                    boxed(FixedLif::new(config, threshold, v_rest, v_reset, tau, config.seed.wrapping_add(n_built.get())))
+                }
+            }
+        });
+        // in virgola fissa i pesi vengono quantizzati al caricamento, tenendo traccia dell'errore di ogni matrice
+        let mut weight_rng = Rng::new(parameters.fixed_point.map_or(0, |c| c.seed));
+        let mut quantization = vec![];
+        let mut quantize = |weights: &mut Csr<f64>, name: String| {
+            if let Some(config) = parameters.fixed_point {
+                let mut error = QuantizationError::new(&name);
+                weights.map_values(|w| *w = quantize_weight(&config, *w, &mut weight_rng, &mut error));
+                quantization.push(error);
+            }
+        };
+        
+        for (i, config) in parameters.adaptive_threshold.iter().enumerate() {
+            if let Some(config) = config {
+                nn.set_adaptive_threshold(i, *config)?;
+            }
+        }
         
-        for i in 0..nn.neural_layers.len() {
-            match nn.connect(i,i,parameters.intra_layer_weights[i].clone()){
-                Ok(_) => continue,
-                Err(e) => return Err(e)
+        // ritardi del layer i-esimo, None se il file non specifica la matrice
+        let delays = |matrices: &Option<Vec<MatrixSpec<u32>>>, i: usize| -> Result<Option<Csr<u32>>, SNNError> {
+            match matrices {
+                None => Ok(None),
+                Some(m) => match m.get(i) {
+                    None => Err(SNNError::BadFormatError(format!("Missing delay matrix for layer [{i}]"))),
+                    Some(d) => Ok(Some(d.load(true)?)),
+                },
             }
+        };
+        for i in 0..nn.neural_layers.len() {
+            let mut weights = parameters.intra_layer_weights[i].load(false)?;
+            quantize(&mut weights, format!("intra_layer_weights[{i}]"));
+            nn.connect(i,i,&weights, delays(&parameters.intra_layer_delays, i)?.as_ref())?;
         }
         for i in 0..nn.neural_layers.len()-1 {
-            match nn.connect(i,i+1,parameters.input_weights[i+1].clone()){
-                Ok(_) => continue,
-                Err(e) => return Err(e)
+            let mut weights = parameters.input_weights[i+1].load(false)?;
+            quantize(&mut weights, format!("input_weights[{}]", i + 1));
+            nn.connect(i,i+1,&weights, delays(&parameters.input_delays, i+1)?.as_ref())?;
+        }
+
+        // ogni neurone del primo layer riceve una sinapsi da ogni input della matrice densa, anche con peso nullo
+        let mut weights = parameters.input_weights[0].load(true)?;
+        quantize(&mut weights, "input_weights[0]".to_string());
+        nn.quantization = quantization;
+        match parameters.encoder {
+            None => nn.connect_inputs(&parameters.inputs,&weights, delays(&parameters.input_delays, 0)?.as_ref())?,
+            Some(encoding) => {
+                let input_layer = InputLayer::from_analog_file(&parameters.inputs, &encoding)?;
+                nn.connect_input_layer(input_layer, &weights, delays(&parameters.input_delays, 0)?.as_ref())?
             }
         }
 
-        let res = nn.connect_inputs(&parameters.inputs,parameters.input_weights[0].clone());
-        match res {
-            Ok(_) => {},
-            Err(e) => return Err(e)
+        for config in parameters.stdp {
+            nn.set_stdp(config)?;
+        }
+        for config in parameters.stp {
+            nn.set_stp(config)?;
+        }
+        for config in parameters.kinetics {
+            nn.set_kinetics(config)?;
+        }
+        for fault in parameters.faults {
+            nn.add_fault(fault)?;
         }
         let om = OutputMonitor::new(last_layer_len);
 
         nn.connect_output(om);
 
-        return Ok(nn);   
-    }   
+        Ok(nn)
+    }
+
+    pub fn set_engine(&mut self, engine: Engine) {
+        // sceglie il motore di simulazione usato dalle chiamate successive a run
+        self.engine = engine;
+    }
 
-    pub fn run(self,output_file: &str) {
-        // lancia la simulazione di tutta la rete neurale, wrapper di tutti i metodi di run 
-        
-        // avvia tutti gli input layer e colleziona gli handler per fare join in caso di successo, None altrimenti (cambiare Option in Result)
-        let tid_input = match self.input_layer {
-            None => panic!("Use connect inputs - Input layer not connected"),
-            Some(il) => il.emit_spikes(),
+    pub fn simulate(&mut self) -> Result<Vec<i32>, SNNError> {
+        // lancia la simulazione di tutta la rete neurale con il motore selezionato e restituisce il numero di spike di ogni
+        // neurone dell'ultimo layer. Input layer e output monitor restano collegati, quindi la rete si può simulare di nuovo.
+        Ok(self.simulate_record()?.counts)
+    }
+
+    pub fn simulate_record(&mut self) -> Result<OutputRecord, SNNError> {
+        // come simulate, ma restituisce anche il passo della prima spike di ogni neurone dell'ultimo layer
+        let input_layer = match self.input_layer.take() {
+            None => return Err(SNNError::InconnectedInput("Use connect inputs - Input layer not connected".to_string())),
+            Some(il) => il,
         };
-        
-        // lancia il metodo che riceve le spike di output dell'ultimo layer, panic se non è connesso ( cambiare option in Result ) 
-        let tid_output = match self.output_monitor {
-            None => panic!("Use connect output - Output monitor not connected"),
-            Some(om) => om.run(),
+        self.rasters.iter_mut().for_each(|r| r.clear());
+        self.probes_mut().for_each(|p| p.clear());
+        let result = self.simulate_inputs(&input_layer);
+        self.input_layer = Some(input_layer);
+        result
+    }
+
+    pub fn run_batch(&mut self, samples: &[Sample], presentation: usize, rest: usize) -> Result<Vec<Vec<i32>>, SNNError> {
+        // numero di spike di ogni neurone dell'ultimo layer per ogni campione, vedi run_batch_records
+        Ok(self.run_batch_records(samples, presentation, rest)?.into_iter().map(|r| r.counts).collect())
+    }
+
+    pub fn run_batch_records(&mut self, samples: &[Sample], presentation: usize, rest: usize) -> Result<Vec<OutputRecord>, SNNError> {
+        /*
+         * Presenta alla rete un campione alla volta e restituisce il numero di spike di ogni neurone dell'ultimo layer per ogni
+         * campione (run_batch) oppure quanto osservato dal monitor (run_batch_records), con i passi contati dall'inizio del
+         * campione. Ogni treno di spike viene troncato o completato con zeri fino a presentation passi, seguiti da rest passi
+         * senza spike. Prima di ogni campione lo stato dei neuroni e delle sinapsi torna a riposo, mentre i parametri appresi
+         * (pesi STDP e soglie adattive) vengono mantenuti. I raster registrano i campioni uno di seguito all'altro, il campione
+         * n comincia dopo n * (presentation + rest) passi, e lo stesso vale per le sonde.
+         */
+        // numero di input atteso dal primo layer
+        let n_inputs = match self.input_layer.as_ref() {
+            Some(il) => il.inputs.len(),
+            None => self.neural_layers[0].neurons.iter().flat_map(|n| n.synapses.iter())
+                .filter(|s| s.get_projection() == Projection::Input)
+                .map(|s| s.get_source() + 1)
+                .max()
+                .unwrap_or(0),
         };
-        
-        // lancia tutti i neuroni di ogni layer, cambiare il metodo run neurons in modo che restituisca un Result<Error, Ok(Vec<Handle>)
-        let mut v = vec![];
-        
-        for l in self.neural_layers {
-            v.push(l.run_neurons());
+        let mut records = Vec::with_capacity(samples.len());
+        self.rasters.iter_mut().for_each(|r| r.clear());
+        self.probes_mut().for_each(|p| p.clear());
+        for (n, sample) in samples.iter().enumerate() {
+            if sample.spikes.len() != n_inputs {
+                return Err(SNNError::BadFormatError(format!(
+                    "Sample [{n}] has {} inputs but the network has {n_inputs}", sample.spikes.len()
+                )));
+            }
+            self.reset_state();
+            self.rasters.iter_mut().for_each(|r| r.set_offset(n * (presentation + rest)));
+            self.probes_mut().for_each(|p| p.set_offset(n * (presentation + rest)));
+            let input_layer = InputLayer::from_spikes((0..n_inputs).map(|i| sample.train(i, presentation, rest)).collect());
+            records.push(self.simulate_inputs(&input_layer)?);
         }
+        Ok(records)
+    }
 
+    pub fn add_raster(&mut self, target: RasterTarget, capacity: Option<usize>) -> Result<usize, SNNError> {
+        // collega un raster all'input layer o a un layer neurale e ne restituisce l'indice, capacity limita il numero di eventi
+        // conservati (gli ultimi)
+        if let RasterTarget::Layer(l) = target {
+            if l >= self.neural_layers.len() {
+                return Err(SNNError::OutOfIndexError(format!("Trying to record layer [{l}] but the net has only {} layers", self.neural_layers.len())));
+            }
+        }
+        self.rasters.push(SpikeRaster::new(target, capacity));
+        Ok(self.rasters.len() - 1)
+    }
 
-        
-        let mut i = 0;
-        // join dei vari thread
-        for tid in tid_input {
-            let r = tid.join();
-            match r {
-                Ok(_) => println!("\t\t- input thread[{}]: OK", i),
-                Err(e) => println!("error {:?} during join neural_netowork run() method", e),
+    pub fn raster(&self, id: usize) -> Option<&SpikeRaster> {
+        // raster registrato durante l'ultima simulazione
+        self.rasters.get(id)
+    }
+
+    pub fn add_probe(&mut self, layer: usize, neuron: usize, every: usize) -> Result<(), SNNError> {
+        // collega una sonda al neurone indicato, che ne registra le variabili di stato ogni every passi (every = 0 equivale a 1).
+        // Una sonda già presente viene sostituita
+        let n_layers = self.neural_layers.len();
+        let l = match self.neural_layers.get_mut(layer) {
+            None => return Err(SNNError::OutOfIndexError(format!("Trying to probe layer [{layer}] but the net has only {n_layers} layers"))),
+            Some(l) => l,
+        };
+        let n_neurons = l.neurons.len();
+        match l.neurons.get_mut(neuron) {
+            None => Err(SNNError::OutOfIndexError(format!("Trying to probe neuron [{neuron}] but layer [{layer}] has only {n_neurons} neurons"))),
+            Some(n) => {
+                n.set_probe(every);
+                Ok(())
             }
-            i += 1;
         }
+    }
+
+    pub fn add_fault(&mut self, fault: Fault) -> Result<(), SNNError> {
+        // inietta un guasto nel neurone indicato (o in una delle sue sinapsi), applicato a ogni simulazione successiva
+        let n_layers = self.neural_layers.len();
+        let l = match self.neural_layers.get_mut(fault.layer) {
+            None => return Err(SNNError::OutOfIndexError(format!("Fault on layer [{}] but the net has only {n_layers} layers", fault.layer))),
+            Some(l) => l,
+        };
+        let n_neurons = l.neurons.len();
+        match l.neurons.get_mut(fault.neuron) {
+            None => Err(SNNError::OutOfIndexError(format!("Fault on neuron [{}] but layer [{}] has only {n_neurons} neurons", fault.neuron, fault.layer))),
+            Some(n) => n.add_fault(fault),
+        }
+    }
+
+    pub fn clear_faults(&mut self) {
+        // rimuove tutti i guasti, i valori già corrotti (parametri e pesi) restano tali
+        for layer in self.neural_layers.iter_mut() {
+            layer.neurons.iter_mut().for_each(|n| n.clear_faults());
+        }
+    }
 
-        for tids in v {
-            for tid in tids {
-                let r = tid.join();
-                match r {
-                    Ok(_) => println!("\t\t- neuron thread: OK"),
-                    Err(e) => println!("error {:?} during join neural_netowork run() method", e),
-                }
+    pub fn quantization_errors(&self) -> &[QuantizationError] {
+        // errore di quantizzazione dei pesi caricati in virgola fissa, nell'ordine di caricamento delle matrici
+        &self.quantization
+    }
+
+    pub fn shape(&self) -> Vec<Vec<usize>> {
+        // numero di sinapsi in ingresso di ogni neurone, per layer
+        self.neural_layers.iter().map(|l| l.neurons.iter().map(|n| n.synapses.len()).collect()).collect()
+    }
+
+    pub fn fault_components(&mut self) -> Vec<Vec<Vec<FaultComponent>>> {
+        // componenti su cui si possono iniettare guasti in ogni neurone, per layer (vedi Neuron::fault_components)
+        self.neural_layers.iter_mut().map(|l| l.neurons.iter_mut().map(|n| n.fault_components()).collect()).collect()
+    }
+
+    pub fn probe(&self, layer: usize, neuron: usize) -> Option<&StateProbe> {
+        // sonda del neurone registrata durante l'ultima simulazione, None se il neurone non è osservato
+        self.neural_layers.get(layer)?.neurons.get(neuron)?.probe()
+    }
+
+    fn probes_mut(&mut self) -> impl Iterator<Item = &mut StateProbe> {
+        self.neural_layers.iter_mut().flat_map(|l| l.neurons.iter_mut()).filter_map(|n| n.probe_mut())
+    }
+
+    pub fn reset_state(&mut self) {
+        // riporta a riposo lo stato di tutti i neuroni e delle loro sinapsi
+        for layer in self.neural_layers.iter_mut() {
+            for neuron in layer.neurons.iter_mut() {
+                neuron.reset_state();
             }
         }
-        // TODO: handle join 
-        let result = tid_output.join();
-        match result {
+    }
+
+    fn simulate_inputs(&mut self, input_layer: &InputLayer) -> Result<OutputRecord, SNNError> {
+        // simula la rete con il motore selezionato usando input_layer come stimolo
+        for raster in self.rasters.iter_mut().filter(|r| r.target() == RasterTarget::Input) {
+            raster.record_inputs(input_layer);
+        }
+        let output_monitor = match self.output_monitor.take() {
+            None => return Err(SNNError::InconnectedOutput("Use connect output - Output monitor not connected".to_string())),
+            Some(om) => om,
+        };
+        let n_outputs = output_monitor.n_outputs();
+        self.neural_layers.iter_mut().flat_map(|l| l.neurons.iter_mut()).for_each(|n| n.rewind());
+        let result = match self.engine {
+            Engine::Threaded => threaded_engine::run(input_layer, &mut self.neural_layers, output_monitor, &mut self.rasters),
+            Engine::ClockDriven => clock_engine::run(input_layer, &mut self.neural_layers, output_monitor, &mut self.rasters),
+            Engine::EventDriven => event_engine::run(input_layer, &mut self.neural_layers, output_monitor, &mut self.rasters),
+            Engine::Parallel => parallel_engine::run(input_layer, &mut self.neural_layers, output_monitor, &mut self.rasters),
+        };
+        // il monitor viene consumato dalla simulazione, ne viene collegato uno nuovo per la successiva
+        self.output_monitor = Some(OutputMonitor::new(n_outputs));
+        result
+    }
+
+    pub fn run(&mut self,output_file: &str) {
+        // wrapper di simulate che stampa e scrive su output_file il numero di spike dei neuroni dell'ultimo layer
+        match self.simulate() {
             Ok(counted_output) => print(counted_output,output_file),
             Err(e) => panic!("{:?}",e)
         };
     }
 
-    pub fn connect(&mut self, from: usize, to: usize, weights: Vec<Vec<f64>>) -> Result<(), SNNError>{
+    pub fn set_adaptive_threshold(&mut self, layer: usize, config: AdaptiveThresholdConfig) -> Result<(), SNNError> {
+        // attiva la soglia omeostatica su tutti i neuroni del layer
+        let n_layers = self.neural_layers.len();
+        match self.neural_layers.get_mut(layer) {
+            None => Err(SNNError::OutOfIndexError(format!("Trying to set the adaptive threshold of layer [{layer}] but the net has only {n_layers} layers"))),
+            Some(l) => {
+                for neuron in l.neurons.iter_mut() {
+                    neuron.set_adaptive_threshold(config);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn set_stdp(&mut self, config: StdpConfig) -> Result<(), SNNError> {
+        // attiva l'apprendimento STDP su tutte le sinapsi della proiezione indicata, da chiamare dopo i metodi connect
+        let n_layers = self.neural_layers.len();
+        let layer = config.layer;
+        if layer >= n_layers {
+            return Err(SNNError::OutOfIndexError(format!("Trying to set STDP on layer [{layer}] but the net has only {n_layers} layers")));
+        }
+        if config.w_min > config.w_max {
+            return Err(SNNError::BadFormatError(format!("STDP on layer [{layer}]: w_min is greater than w_max")));
+        }
+        if !config.enabled {
+            return Ok(());
+        }
+        for neuron in self.neural_layers[layer].neurons.iter_mut() {
+            for synapse in neuron.synapses.iter_mut() {
+                if synapse.get_projection() == config.projection {
+                    synapse.set_stdp(config);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_stp(&mut self, config: StpConfig) -> Result<(), SNNError> {
+        // attiva la plasticità a breve termine su tutte le sinapsi della proiezione indicata, da chiamare dopo i metodi connect
+        let n_layers = self.neural_layers.len();
+        let layer = config.layer;
+        if layer >= n_layers {
+            return Err(SNNError::OutOfIndexError(format!("Trying to set STP on layer [{layer}] but the net has only {n_layers} layers")));
+        }
+        if !(0.0..=1.0).contains(&config.u) || config.tau_rec <= 0.0 {
+            return Err(SNNError::BadFormatError(format!("STP on layer [{layer}]: U must be in [0, 1] and tau_rec positive")));
+        }
+        for neuron in self.neural_layers[layer].neurons.iter_mut() {
+            for synapse in neuron.synapses.iter_mut() {
+                if synapse.get_projection() == config.projection {
+                    synapse.set_stp(config);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_kinetics(&mut self, config: KineticsConfig) -> Result<(), SNNError> {
+        // sostituisce il salto istantaneo con una cinetica sinaptica su tutte le sinapsi della proiezione indicata
+        let n_layers = self.neural_layers.len();
+        let layer = config.layer;
+        if layer >= n_layers {
+            return Err(SNNError::OutOfIndexError(format!("Trying to set synaptic kinetics on layer [{layer}] but the net has only {n_layers} layers")));
+        }
+        if !config.is_valid() {
+            return Err(SNNError::BadFormatError(format!("Kinetics on layer [{layer}]: time constants must be positive and tau_rise < tau_decay")));
+        }
+        for neuron in self.neural_layers[layer].neurons.iter_mut() {
+            for synapse in neuron.synapses.iter_mut() {
+                if synapse.get_projection() == config.projection {
+                    synapse.set_kinetics(config);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn thresholds(&self) -> Vec<Vec<f64>> {
+        // soglie effettive di tutti i neuroni, nello stesso formato del campo thresholds del file JSON
+        self.neural_layers
+            .iter()
+            .map(|l| l.neurons.iter().map(|n| n.threshold()).collect())
+            .collect()
+    }
+
+    pub fn save_json(&self, template: &str, path: &str) -> Result<(), SNNError> {
+        /*
+         * Scrive in path una copia del file JSON template (tipicamente quello passato a from_json) in cui i parametri appresi
+         * durante la simulazione (soglie e pesi) sostituiscono quelli originali. I meccanismi di adattamento vengono rimossi, in
+         * modo da ottenere una rete congelata che si può caricare di nuovo con from_json. Errore se un layer con soglia adattiva
+         * usa un modello che non legge la soglia da thresholds, perché l'adattamento andrebbe perso.
+         */
+        let file = match File::open(template) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot open file {template}."))),
+            Ok(f) => f,
+        };
+        let mut parameters: serde_json::Value = match serde_json::from_reader(file) {
+            Err(e) => return Err(SNNError::BadFormatError(format!("File :{template}\nERROR:{e}"))),
+            Ok(p) => p,
+        };
+        let n_layers = self.neural_layers.len();
+        let models: Result<Vec<ModelConfig>, _> = match (parameters.get("layer_models"), parameters.get("model")) {
+            (Some(models), _) => serde_json::from_value(models.clone()),
+            (None, Some(model)) => serde_json::from_value(model.clone()).map(|m| vec![m; n_layers]),
+            (None, None) => Ok(vec![ModelConfig::default(); n_layers]),
+        };
+        let models = match models {
+            Err(e) => return Err(SNNError::BadFormatError(format!("File :{template}\nERROR:{e}"))),
+            Ok(m) => m,
+        };
+        for (l, layer) in self.neural_layers.iter().enumerate() {
+            let adaptive = layer.neurons.iter().any(|n| n.theta().is_some());
+            if adaptive && !models.get(l).is_some_and(ModelConfig::reads_threshold) {
+                return Err(SNNError::BadFormatError(format!(
+                    "Layer [{l}] has an adaptive threshold but its model does not read thresholds, the adaptation cannot be exported"
+                )));
+            }
+        }
+
+        let fields = match parameters.as_object_mut() {
+            None => return Err(SNNError::BadFormatError(format!("{template} does not contain a JSON object"))),
+            Some(fields) => fields,
+        };
+        fields.insert("thresholds".to_string(), serde_json::json!(self.thresholds()));
+        fields.remove("adaptive_threshold");
+        fields.remove("stdp");
+
+        // i pesi vengono scritti nello stesso formato letto da from_json, input_weights[0] è indicizzato [neurone][input]. Le
+        // matrici sparse vengono riscritte per intero come triplette
+        let mut sparse: BTreeMap<String, Vec<(usize, usize, f64)>> = BTreeMap::new();
+        for (l, layer) in self.neural_layers.iter().enumerate() {
+            for (j, neuron) in layer.neurons.iter().enumerate() {
+                for synapse in &neuron.synapses {
+                    let i = synapse.get_source();
+                    let (matrix, row, col) = match synapse.get_projection() {
+                        Projection::Input if l == 0 => ("/input_weights/0".to_string(), j, i),
+                        Projection::Input => (format!("/input_weights/{l}"), i, j),
+                        Projection::Intra => (format!("/intra_layer_weights/{l}"), i, j),
+                    };
+                    match parameters.pointer_mut(&matrix) {
+                        Some(serde_json::Value::Object(_)) => sparse.entry(matrix).or_default().push((row, col, synapse.get_weight())),
+                        _ => match parameters.pointer_mut(&format!("{matrix}/{row}/{col}")) {
+                            None => return Err(SNNError::BadFormatError(format!("{template} has no weight at {matrix}/{row}/{col}"))),
+                            Some(w) => *w = serde_json::json!(synapse.get_weight()),
+                        },
+                    }
+                }
+            }
+        }
+        for (matrix, triplets) in sparse {
+            if let Some(fields) = parameters.pointer_mut(&matrix).and_then(|m| m.as_object_mut()) {
+                fields.remove("file");
+                fields.insert("triplets".to_string(), serde_json::json!(triplets));
+            }
+        }
+
+        let output_file = match File::create(path) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot create file {path}."))),
+            Ok(f) => f,
+        };
+        match serde_json::to_writer_pretty(output_file, &parameters) {
+            Err(e) => Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub fn connect(&mut self, from: usize, to: usize, weights: &Csr<f64>, delays: Option<&Csr<u32>>) -> Result<(), SNNError>{
         /*
          * Questo metodo connette il layer from con il layer to, se i valori coincidono significa che si stanno collegando neuroni dello stesso layer
          * e quindi si stano creando sinapsi inibitorie. In generale si utilizza una matrice di pesi in il primo indice indica il neurone del layer a
          * 'sinistra' (from), mentre il secondo quello a destra (to), per specificare due neuroni non collegati utilizzare *None*. Nel caso di sinapsi
          * inibitorie utilizzare una matrice quadrata con diagonale pari a None.
+         * La matrice opzionale delays ha la stessa forma di weights e contiene il ritardo di ogni sinapsi in passi di simulazione.
+         * Le matrici sono in formato CSR, per cui il tempo di connessione è proporzionale al numero di sinapsi.
          */
         let n_layers = self.neural_layers.len();
         // check if the two parameters are conform with the net's dimension
@@ -167,86 +601,82 @@ impl NeuralNetwork {
             );
         }
 
-        for (i, row) in weights.iter().enumerate() {
-            // for each neuron connected to the sender add the receiver end
-            for (j, weight) in row.iter().enumerate() {
-                let (tx, rx) = channel();
-                if *weight != 0.0 {
-                        match self.neural_layers[to].add_synapse(j, *weight, rx){
-                            Ok(_) => {
-                                // add the sender (tx) part of the channel to the 'to' layer
-                                self.neural_layers[from].add_sender(i, tx);
-                            }, 
-                            Err(e) => return Err(e)
-                        }
-                    }
+        let projection = if from == to { Projection::Intra } else { Projection::Input };
+
+        // for each neuron connected to the sender add the receiver end
+        for (i, j, weight) in weights.iter() {
+            if weight != 0.0 {
+                let delay = delay_at(delays, i, j)?;
+                // la sinapsi ricorda il neurone sorgente, i canali vengono creati dal motore di simulazione
+                self.neural_layers[to].add_synapse(j, weight, projection, i, delay)?;
             }
         }
         Ok(())
     }
 
-    pub fn connect_inputs(&mut self, filename: &str, weights: Vec<Vec<f64>>) -> Result<(), SNNError>{
+    pub fn connect_inputs(&mut self, filename: &str, weights: &Csr<f64>, delays: Option<&Csr<u32>>) -> Result<(), SNNError>{
         /*
          * Connette il layer di input con il primo layer (in posizione 0) della rete neurale. Questo metodo fallisce se non sono ancora stati
-         * aggiunti dei layer alla rete oppure se ci sono problemi con la lettura del file.
+         * aggiunti dei layer alla rete oppure se ci sono problemi con la lettura del file. Come weights, delays è indicizzata [neurone][input].
          */
-        if (&self.neural_layers).len() == 0 {
-            panic!("Cannot link input with first layer, the layer does not exist.")
-        }
-        
         // crea il layer di input a partire dai file specificati
         //  self.input_layer = Some(InputLayer::from_files(filenames));
-        self.input_layer = Some(InputLayer::from_file(filename,'\n').unwrap());
-        // per ogni file
-        
-        for (i, row) in weights.iter().enumerate() {
-            // sender: lato input layer
-            // receiver: lato neuron layer
-            for (j, weight) in row.iter().enumerate() {
-                let (tx, rx) = channel::<Spike>();
-                self.input_layer.as_mut().unwrap().add_sender_to(j, tx);
-
-                let res = self.neural_layers[0].add_synapse(i, *weight, rx);
-                if res.is_err(){
-                    return res;
-                }
-            }
+        self.connect_input_layer(InputLayer::from_file(filename,'\n').unwrap(), weights, delays)
+    }
+
+    pub fn connect_input_layer(&mut self, input_layer: InputLayer, weights: &Csr<f64>, delays: Option<&Csr<u32>>) -> Result<(), SNNError>{
+        // come connect_inputs, con un input layer già costruito (ad esempio codificando valori analogici)
+        if self.neural_layers.is_empty() {
+            panic!("Cannot link input with first layer, the layer does not exist.")
+        }
+        self.input_layer = Some(input_layer);
+
+        // ogni elemento memorizzato in weights diventa una sinapsi, anche con peso nullo
+        for (i, j, weight) in weights.iter() {
+            let delay = delay_at(delays, i, j)?;
+            self.neural_layers[0].add_synapse(i, weight, Projection::Input, j, delay)?;
         }
         Ok(())
     }
 
     // TODO: return un errore al posto del panic, OK(()) se tutto funziona 
-    pub fn connect_output(&mut self, mut output_monitor: OutputMonitor){
+    pub fn connect_output(&mut self, output_monitor: OutputMonitor){
         // Connette l'ultimo layer con un output monitor, consuma l'ouput monitor e lo assegna alla rete. 
         
         // controllo che esista almeno un layer 
-        if self.neural_layers.len() == 0{
+        if self.neural_layers.is_empty() {
             panic!("add at least a layer before adding the output monitor");
         }
-        // calcolo index ultimo layer
-        let last_layer =  self.neural_layers.len() - 1 ;
-
-        
-        for neuron in self.neural_layers[last_layer].neurons.iter_mut(){
-            // assegna ad ogni neurone l'estremità di sender e aggiunge all'output monitor i receiver
-            let (tx, rx) = channel::<Spike>();    
-            neuron.output.push(tx.clone());
-            output_monitor.add_receiver(rx);
-        }
-
+        // i receiver vengono aggiunti dal motore multi-thread all'avvio della simulazione
         self.output_monitor = Some(output_monitor);
 
     }
 
-    pub fn to_string(&self) -> String {
-        format!(
+}
+
+impl fmt::Display for NeuralNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
             "Neural Network with:\n\t- {}\n\t- [{}] neural layers",
-            self.input_layer.as_ref().unwrap().to_string(),
+            self.input_layer.as_ref().unwrap(),
             self.neural_layers.len()
         )
     }
+}
 
-
+fn delay_at(delays: Option<&Csr<u32>>, i: usize, j: usize) -> Result<u32, SNNError> {
+    // ritardo della sinapsi (i, j), almeno un passo: il passo di trasporto della rete originale è il ritardo 1, per cui una
+    // spike emessa al passo t viene integrata al passo t + ritardo - 1. Le matrici sparse indicano solo i ritardi diversi da 1
+    match delays {
+        None => Ok(1),
+        Some(d) if i >= d.rows() || j >= d.cols() => Err(SNNError::OutOfIndexError(format!("Missing delay for synapse [{i}][{j}]"))),
+        Some(d) => match d.get(i, j) {
+            None => Ok(1),
+            Some(0) => Err(SNNError::BadFormatError(format!("Delay of synapse [{i}][{j}] must be at least 1"))),
+            Some(delay) => Ok(delay),
+        },
+    }
 }
 
 pub fn print(result: Vec<i32>,path: &str) {
@@ -255,6 +685,6 @@ pub fn print(result: Vec<i32>,path: &str) {
     }
     let mut output_file = File::create(path).unwrap();
     for o in result {
-        write!(output_file, "{}\n", o.to_string()).expect("Unable to write output length");
+        writeln!(output_file, "{}", o).expect("Unable to write output length");
     }
 }
@@ -1,8 +1,14 @@
 use std::sync::{Arc, Barrier};
 
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{Receiver, Sender};
 
 use super::errors::SNNError;
+use super::fault::{Fault, FaultComponent};
+use super::hardware::datapath_components;
+use super::models::NeuronDynamics;
+use super::probe::StateProbe;
+use super::refractory::{Refractory, RefractoryConfig};
+use super::threshold::{AdaptiveThreshold, AdaptiveThresholdConfig};
 use super::{synapse::Synapse, spike::Spike};
 /*
 Classe che contiene l'intelligenza della rete, attraverso i channel i vari neuroni comunicano fra di loro, si utilizzano i Sender
@@ -10,91 +16,309 @@ con capacità 0 (canali rendez-vous) in modo da non utilizzare altre memory barr
 e ultimo layer.
 */
 pub struct Neuron {
-    // neural network parameters
-    v_threshold: f64,
-    v_rest: f64,
-    v_reset: f64,
-    // LIR model function signature, maybe to be generalized
-    model: Arc<dyn Fn(i32, i32, f64, f64, f64, Vec<f64>) -> f64 + Send + Sync + 'static>,
-    // last 'neuron fired' tension
-    v_mem_old: f64,
-    // last unit time at which the neuron fired
-    ts_1: i32,
+    // modello del neurone insieme al suo stato (potenziale di membrana e variabili ausiliarie)
+    model: Box<dyn NeuronDynamics>,
+    // periodo refrattario assoluto e relativo
+    refractory: Refractory,
+    // soglia omeostatica, None se la soglia è fissa
+    adaptive_threshold: Option<AdaptiveThreshold>,
     // ts NON è il tempo globale, non è necessario avere un contatore globale perchè la rete ha bisogno solo di differenze temporali (1 - 0) == (12 - 11)
-    // ts è un contatore locale al neurone (un'unità indietro rispetto al layer precedente se si considera un tempo t della simulazione)
+    // ts è un contatore locale al neurone: il numero di passi già simulati, incluso quelli saltati da un motore event-driven
     ts: i32,
-    // channels' ends with associated weight 
+    // sinapsi in ingresso, con peso e stato dei meccanismi sinaptici
     pub synapses: Vec<Synapse>,
-    // neuron output
-    pub output: Vec<Sender<Spike>>,
-
-    tao: f64,
     // formato: l#n#, dove il primo # indica il numero del layer, mentre il secondo indica il numero del neurone all'interno del layer
     name: i32,
+    // sonda sulle variabili di stato, None se il neurone non è osservato
+    probe: Option<StateProbe>,
+    // guasti iniettati nei parametri del modello o nei pesi delle sinapsi
+    faults: Vec<Fault>,
 }
 
 impl Neuron {
     // Neuron constructor
-    pub fn new(
-        v_threshold: f64,
-        v_rest: f64,
-        v_reset: f64,
-        tao: f64,
-        model: fn(i32, i32, f64, f64, f64, Vec<f64>) -> f64,
-        name: i32,
-    ) -> Self {
+    pub fn new<D: Into<Box<dyn NeuronDynamics>>>(model: D, refractory: RefractoryConfig, name: i32) -> Self {
         Self {
-            v_threshold,
-            v_rest,
-            v_reset,
             // at the beginning the net is resting
-            v_mem_old: v_rest,
-            ts_1: 0,
+            model: model.into(),
+            refractory: Refractory::new(refractory),
+            adaptive_threshold: None,
             ts: 0,
-            tao,
-            model: Arc::new(model),
             synapses: vec![],
-            output: vec![],
             name,
+            probe: None,
+            faults: vec![],
         }
     }
 
-    fn read_spikes(&self) -> Result<Vec<f64>, SNNError> {
-        // legge gli impulsi provenienti dal layer precedente (sia neurale che di input)
+    pub fn set_adaptive_threshold(&mut self, config: AdaptiveThresholdConfig) {
+        self.adaptive_threshold = Some(AdaptiveThreshold::new(config));
+    }
 
-        // vettore che contiene (w_i * s_i) dove s_i è 0 o 1 e w_i è il peso della connessione
-        let mut weighted_inputs = vec![];
-        
-        // per ogni connessione in ingresso 
-        for syanpse in &self.synapses {
-             
-            // a ts = 0 (inizio della simulazione) nessun neurone scatta e quindi non deve aspettare sulle sinapsi inibitorie
-            if syanpse.get_weight() < 0.0 && self.ts == 0 {
+    pub fn set_probe(&mut self, every: usize) {
+        self.probe = Some(StateProbe::new(every));
+    }
+
+    pub fn probe(&self) -> Option<&StateProbe> {
+        self.probe.as_ref()
+    }
+
+    pub fn probe_mut(&mut self) -> Option<&mut StateProbe> {
+        self.probe.as_mut()
+    }
+
+    pub fn sample(&mut self, ts: usize) {
+        // registra lo stato dopo l'aggiornamento del passo ts, se il neurone ha una sonda e il passo è un multiplo del periodo
+        let Some(probe) = self.probe.as_mut() else { return };
+        if !probe.is_due(ts) {
+            return;
+        }
+        let mut variables = self.model.state_variables();
+        if let Some(at) = self.adaptive_threshold {
+            variables.push(("theta", at.theta()));
+        }
+        probe.record(ts, variables);
+    }
+
+    pub fn add_fault(&mut self, mut fault: Fault) -> Result<(), SNNError> {
+        // aggiunge un guasto al neurone, errore se il componente non esiste
+        if fault.bit >= 64 {
+            return Err(SNNError::OutOfIndexError(format!("Fault on bit [{}] but values have only 64 bits", fault.bit)));
+        }
+        if !fault.kind.is_permanent() && fault.time == 0 {
+            return Err(SNNError::BadFormatError("Bit-flip faults need a time step, starting from 1".to_string()));
+        }
+        if let FaultComponent::Datapath { .. } = fault.component {
+            // i guasti sui segnali interni vengono applicati dal datapath del modello durante l'aggiornamento
+            return match self.model.datapath() {
+                None => Err(SNNError::BadFormatError("The neuron model has no hardware datapath".to_string())),
+                Some(datapath) => datapath.add_fault(fault),
+            };
+        }
+        match (fault.component, fault.synapse) {
+            (FaultComponent::Weight, None) => {
+                return Err(SNNError::BadFormatError("Weight faults need a synapse index".to_string()));
+            }
+            (FaultComponent::Weight, Some(k)) if k >= self.synapses.len() => {
+                return Err(SNNError::OutOfIndexError(format!("Fault on synapse [{k}] but the neuron has only {} synapses", self.synapses.len())));
+            }
+            (FaultComponent::Weight, _) => {}
+            (component, _) => {
+                if self.model.register(component).is_none() {
+                    return Err(SNNError::BadFormatError(format!("The neuron model has no {component:?} to corrupt")));
+                }
+                fault.synapse = None;
+            }
+        }
+        self.faults.push(fault);
+        Ok(())
+    }
+
+    pub fn clear_faults(&mut self) {
+        // rimuove i guasti, i valori già corrotti non vengono ripristinati
+        self.faults.clear();
+        if let Some(datapath) = self.model.datapath() {
+            datapath.clear_faults();
+        }
+    }
+
+    pub fn fault_components(&mut self) -> Vec<FaultComponent> {
+        // componenti accettati da add_fault: i valori esposti dal modello, i pesi se il neurone ha sinapsi e i segnali del
+        // datapath se il modello ne ha uno
+        let registers = [FaultComponent::VThreshold, FaultComponent::VRest, FaultComponent::VReset, FaultComponent::VMem];
+        let mut components: Vec<FaultComponent> = registers.into_iter().filter(|c| self.model.register(*c).is_some()).collect();
+        if !self.synapses.is_empty() {
+            components.push(FaultComponent::Weight);
+        }
+        if self.model.datapath().is_some() {
+            components.extend(datapath_components());
+        }
+        components
+    }
+
+    fn apply_faults(&mut self, flip_at: Option<usize>) {
+        // forza i bit dei guasti permanenti e, se flip_at coincide con il passo del guasto, inverte quelli dei bit-flip
+        for f in 0..self.faults.len() {
+            let fault = self.faults[f];
+            if !flip_at.map_or(fault.kind.is_permanent(), |tick| fault.is_active(tick)) {
                 continue;
             }
+            match fault.synapse {
+                Some(k) => {
+                    let synapse = &mut self.synapses[k];
+                    synapse.set_weight(fault.kind.apply(synapse.get_weight(), fault.bit));
+                }
+                None => {
+                    if let Some(value) = self.model.register(fault.component) {
+                        *value = fault.kind.apply(*value, fault.bit);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn lag(&self, k: usize) -> usize {
+        // passi di ritardo con cui la sinapsi k legge le spike della sorgente: come nel motore multi-thread, una sinapsi con peso
+        // negativo al primo passo (dopo i guasti attivi in quel passo) salta la prima lettura e resta indietro di un passo
+        let weight = self.faults
+            .iter()
+            .filter(|fault| fault.synapse == Some(k) && fault.is_active(1))
+            .fold(self.synapses[k].get_weight(), |weight, fault| fault.kind.apply(weight, fault.bit));
+        usize::from(weight < 0.0)
+    }
+
+    pub fn rewind(&mut self) {
+        // riporta il tempo locale a 0 all'inizio di una simulazione, senza toccare lo stato
+        self.ts = 0;
+    }
+
+    pub fn theta(&self) -> Option<f64> {
+        // innalzamento omeostatico corrente della soglia, None se la soglia è fissa
+        self.adaptive_threshold.map(|at| at.theta())
+    }
+
+    pub fn threshold(&self) -> f64 {
+        // soglia effettiva del neurone, comprensiva dell'innalzamento omeostatico ma non di quello refrattario
+        let theta = self.adaptive_threshold.map_or(0.0, |at| at.theta());
+        self.model.v_threshold() + theta
+    }
+
+    pub fn reset_state(&mut self) {
+        // riporta il neurone a riposo tra due stimoli: potenziale, periodo refrattario e stato delle sinapsi. La soglia
+        // adattiva e i pesi sono parametri appresi e vengono mantenuti
+        self.model.reset_state();
+        self.refractory.reset();
+        for synapse in self.synapses.iter_mut() {
+            synapse.reset_state();
+        }
+        self.ts = 0;
+    }
+
+    pub fn collect_inputs<F>(&mut self, mut spike_for: F) -> Result<Vec<f64>, SNNError>
+    where
+        // spike da consegnare alla k-esima sinapsi in questo passo, None se la sinapsi non riceve nulla
+        F: FnMut(usize, &Synapse) -> Result<Option<i8>, SNNError>,
+    {
+        // consegna a ogni sinapsi la spike del passo corrente, indipendentemente da come il motore di simulazione la trasporta
+
+        // vettore che contiene (w_i * s_i) dove s_i è 0 o 1 e w_i è il peso della connessione
+        let mut weighted_inputs = vec![];
 
-            // riceve gli input nella forma di Result<RecvError, Ok(s_i * w_i)
-            match syanpse.receive() {
+        // i guasti del passo vengono applicati prima di leggere i pesi e il potenziale
+        self.apply_faults(Some(self.ts as usize + 1));
+        let v_mem = self.model.v_mem();
+        // per ogni connessione in ingresso 
+        for (k, syanpse) in self.synapses.iter_mut().enumerate() {
+            if let Some(spike) = spike_for(k, syanpse)? {
                 // wi: weighted input
-                Ok(wi) => {
-                    // considera solo gli input != 0
-                    if wi != 0.0 {
-                        weighted_inputs.push(wi)
-                    }
-                    
+                let wi = syanpse.deliver(spike, v_mem);
+                // considera solo gli input != 0
+                if wi != 0.0 {
+                    weighted_inputs.push(wi)
                 }
-                // Comunicazione terminata, canale vuoto o chiuso dall'estremità del sender
-                Err(e) => return Err(e),
             }
         }
-        return Ok(weighted_inputs);
+        Ok(weighted_inputs)
+    }
+
+    pub fn collect_events(&mut self, events: &[(usize, i8)]) -> Vec<f64> {
+        // consegna le spike solo alle sinapsi indicate in events (indice della sinapsi, spike), ordinate per indice. Le altre
+        // sinapsi ricevono implicitamente 0, quindi il risultato coincide con collect_inputs solo se tutte le sinapsi sono passive
+        self.apply_faults(Some(self.ts as usize + 1));
+        let v_mem = self.model.v_mem();
+        let mut weighted_inputs = vec![];
+        for (k, spike) in events {
+            let wi = self.synapses[*k].deliver(*spike, v_mem);
+            if wi != 0.0 {
+                weighted_inputs.push(wi)
+            }
+        }
+        weighted_inputs
+    }
+
+    pub fn is_event_driven(&self) -> bool {
+        // true se il neurone può restare fermo nei passi senza spike in ingresso: il modello evolve solo con il tempo
+        // trascorso, non ci sono periodi refrattari o soglie che decadono e tutte le sinapsi sono passive. Un neurone con una
+        // sonda va osservato a ogni passo, uno con dei guasti li deve applicare al passo giusto: entrambi vengono sempre aggiornati
+        self.model.event_driven()
+            && self.probe.is_none()
+            && self.faults.is_empty()
+            && self.refractory.is_inert()
+            && self.adaptive_threshold.is_none()
+            && self.synapses.iter().all(|s| s.is_passive())
+    }
+
+    pub fn advance(&mut self, steps: u32) {
+        // fa avanzare un neurone event-driven di steps passi senza ingressi
+        self.model.update(&[], steps as f64, 0.0);
+        self.ts += steps as i32;
     }
 
-    pub fn emit_spikes(&self, spike : Spike) -> Result<(), SNNError>{
+    fn read_spikes(&mut self, receivers: &[Receiver<Spike>]) -> Result<Vec<f64>, SNNError> {
+        // legge gli impulsi provenienti dal layer precedente (sia neurale che di input), receivers è parallelo a synapses
+        let ts = self.ts;
+        self.collect_inputs(|k, syanpse| {
+            // a ts = 0 (inizio della simulazione) nessun neurone scatta e quindi non deve aspettare sulle sinapsi inibitorie
+            if syanpse.get_weight() < 0.0 && ts == 0 {
+                return Ok(None);
+            }
+            // Comunicazione terminata, canale vuoto o chiuso dall'estremità del sender
+            match receivers[k].recv() {
+                Ok(spike) => Ok(Some(spike.output)),
+                Err(_) => Err(SNNError::EmptyChannelError("Call the connect before calling the receive method.".to_string())),
+            }
+        })
+    }
+
+    pub fn step(&mut self, weighted_inputs: &[f64]) -> i8 {
+        // un passo di simulazione dati gli ingressi pesati già consegnati dalle sinapsi, restituisce la spike emessa
+        self.ts += 1;
+        if let Some(datapath) = self.model.datapath() {
+            datapath.set_tick(self.ts as usize);
+        }
+        let spike = self.fire(weighted_inputs);
+        // i bit bloccati restano tali anche dopo che il passo ha riscritto il potenziale e i pesi
+        self.apply_faults(None);
+        spike
+    }
+
+    fn fire(&mut self, weighted_inputs: &[f64]) -> i8 {
+
+        // durante il periodo refrattario assoluto gli ingressi letti vengono scartati
+        let refractory = self.refractory.step(1.0);
+        let inputs: &[f64] = if refractory { &[] } else { weighted_inputs };
+        let mut threshold_offset = self.refractory.threshold_offset();
+        if let Some(at) = self.adaptive_threshold.as_mut() {
+            at.step(1.0);
+            threshold_offset += at.theta();
+        }
+        // il modello integra gli ingressi del passo corrente, l'evoluzione senza ingressi dipende dal modello
+        let fired = self.model.update(inputs, 1.0, threshold_offset);
+        if !fired {
+            return 0;
+        }
+        // se il modello supera la soglia resetta lo stato, anche durante il periodo refrattario assoluto: altrimenti Izhikevich e
+        // AdEx resterebbero fermi sul picco fino alla fine del periodo. La spike però viene emessa solo fuori dal periodo
+        self.model.reset();
+        if refractory {
+            return 0;
+        }
+        self.refractory.on_spike();
+        if let Some(at) = self.adaptive_threshold.as_mut() {
+            at.on_spike();
+        }
+        // aggiornamento STDP delle sinapsi in ingresso
+        for synapse in self.synapses.iter_mut() {
+            synapse.on_post_spike();
+        }
+        1
+    }
+
+    pub fn emit_spikes(&self, outputs: &[Sender<Spike>], spike : Spike) -> Result<(), SNNError>{
         // invia 0 o 1 ai neuroni successivi
 
         // per ogni connessione in uscita 
-        for out in &self.output{
+        for out in outputs {
             // invia la spike
             let r = out.send(spike);
             // TODO vedere se c'è un modo di gestire solo il ramo Err, l'Ok non dovrebbe fare nulla  
@@ -105,70 +329,39 @@ impl Neuron {
         }
         Ok(())
     }
-    pub fn run(&mut self, barrier: Arc<Barrier>) -> Result<(), SNNError>{
-        // riceve uno smart pointer a barrier per sincronizzarsi con gli altri neuroni
+
+    pub fn run(&mut self, barrier: Arc<Barrier>, receivers: Vec<Receiver<Spike>>, outputs: Vec<Sender<Spike>>) -> Result<(), SNNError>{
+        // riceve uno smart pointer a barrier per sincronizzarsi con gli altri neuroni, un receiver per ogni sinapsi e i sender
+        // verso i neuroni collegati in uscita (o verso il monitor)
 
         // receiving: true se il layer precedente invia Result Ok, false altrimenti (fine della trasimissione, canale chiuso)
         let mut receiving = true;
+        // ogni simulazione riparte dal tempo locale 0
+        self.ts = 0;
         
         while receiving {
-            // spike received: true se esiste un valore != 0
-            let spike_received;
+            let mut out_spike = 0;
             // vettore di ingressi pesati provenienti dai neuroni di ingresso 
-            let res_weighted_inputs = self.read_spikes();
-
-            match &res_weighted_inputs {
+            let res_weighted_inputs = self.read_spikes(&receivers);
+            match res_weighted_inputs {
                 Err(_) => {
                     // fine della connessione, estremità in ingresso chiusa 
-                    receiving=false;
-                    spike_received = false;
+                    receiving = false;
                 }
-                // true se esiste un input != 0 
-                Ok(weighted_inputs) => spike_received = !weighted_inputs.is_empty(),
-            };
-            let mut out_spike = 0;
-            // 0100001000
-            // ts = 0
-            // ts_1 = 0
-            self.ts += 1;
-            if spike_received {
-                // se esiste una spike diversa da 0, il neurone comincia l'elaborazione
-                let pos_input = res_weighted_inputs.as_ref().unwrap().iter().any(|x| *x > 0.0);
-
-                let out = (*self.model)(
-                    self.ts,
-                    self.ts_1,
-                    self.v_rest,
-                    self.v_mem_old,
-                    self.tao,
-                    // è possibile fare unwrap() perchè altrimenti l'if sarebbe false
-                    res_weighted_inputs.unwrap(),
-                );
-                
-                if pos_input{
-                    self.ts_1 = self.ts;
+                Ok(weighted_inputs) => {
+                    out_spike = self.step(&weighted_inputs);
+                    self.sample(self.ts as usize);
                 }
-                self.v_mem_old = out;
-                // println!("neuron [{}] emits {} at time [{}] --- threshold: {}" , self.name, out, self.ts, self.v_threshold);
-                if out > self.v_threshold {
-                    // se il modello fornisce un valore maggiore della soglia, resetta la tensione di membrana e assegna 1 all'out_spike
-                    // e aggiorna ts_1 a ts
-                    out_spike = 1;
-                    self.v_mem_old = self.v_reset;
-                } 
-                
             }
             // invia la spike a tutti i neuroni di output o al monitor
             // TODO: sarebbe meglio dare un return come Result 
-            let res = self.emit_spikes(Spike::new(out_spike, Some(self.name)));
+            let res = self.emit_spikes(&outputs, Spike::new(out_spike, Some(self.name)));
             
             
             // attendi che gli altri thread facciano output prima di leggere gli input 
             barrier.wait();
-            if res.is_err() {
-                // errore critico, ferma la simulazione (receiver terminato prima del sender)
-                return res;
-            }
+            // errore critico, ferma la simulazione (receiver terminato prima del sender)
+            res?;
             
         }
         Ok(())
@@ -6,6 +6,16 @@ use std::{
 
 use super::errors::SNNError;
 
+/*
+Risultato di una simulazione osservato dal monitor: numero di spike e passo (da 1) della prima spike di ogni neurone.
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutputRecord {
+    pub counts: Vec<i32>,
+    // None se il neurone non ha mai emesso spike
+    pub first_spikes: Vec<Option<usize>>,
+}
+
 /*
 Terminale che si può connettere a un layer per osservarne gli output.
 */
@@ -16,6 +26,8 @@ pub struct OutputMonitor {
     // outputs è il vettore che colleziona il numero di spike a 1 per ogni neurone dell'ultimo layer 
     // corrispondenti a spike.n_neuron. 
     outputs: Vec<i32>,
+    // passo della prima spike di ogni neurone
+    first_spikes: Vec<Option<usize>>,
     // tempo locale al monitor
     ts: i32,
 }
@@ -26,6 +38,7 @@ impl OutputMonitor {
         Self {
             receivers: vec![],
             outputs: vec![0; n_lastlayer],
+            first_spikes: vec![None; n_lastlayer],
             ts: 0,
         }
     }
@@ -35,25 +48,42 @@ impl OutputMonitor {
         self.receivers.push(receiver);
     }
 
+    pub fn record(&mut self, spike: Spike, ts: usize) -> Result<(), SNNError> {
+        // registra una spike dell'ultimo layer emessa al passo ts, indipendentemente dal motore di simulazione che la produce
+        let n_neuron = match spike.n_neuron {
+            Some(index) => index as usize,
+            None => {
+                return Err(SNNError::InconnectedOutput("Connect the last layer with the output layer before calling run".to_string()));
+            }
+        };
+        // aggiorna il vettore in posizione n_neuron con la spike ricevuta (+0 o +1)
+        self.outputs[n_neuron] += spike.output as i32;
+        if spike.output != 0 && self.first_spikes[n_neuron].is_none() {
+            self.first_spikes[n_neuron] = Some(ts);
+        }
+        Ok(())
+    }
+
+    pub fn n_outputs(&self) -> usize {
+        // numero di neuroni osservati
+        self.outputs.len()
+    }
+
+    pub fn into_record(self) -> OutputRecord {
+        // consuma il monitor e restituisce quanto osservato
+        OutputRecord { counts: self.outputs, first_spikes: self.first_spikes }
+    }
+
     pub fn receive(&mut self) -> Result<() , SNNError> {
         // riceve gli impulsi dal layer precedente, se va a buon fine restituisce il vettore di impulsi letti, altrimenti un RecvError
 
         // per ogni ricevitore
-        for receiver in &self.receivers {
+        for k in 0..self.receivers.len() {
             // riceve gli impulsi
-            let out = receiver.recv();
+            let out = self.receivers[k].recv();
             match out {
-                Ok(spike) => {
-                    let n_neuron;
-                    match spike.n_neuron {
-                        Some(index) => n_neuron = index as usize,
-                        None => {
-                            return Err(SNNError::InconnectedOutput("Connect the last layer with the output layer before calling run".to_string()));
-                        }
-                    };
-                    // aggiorna il vettore in posizione n_neuron con la spike ricevuta (+0 o +1)
-                    self.outputs[n_neuron] += spike.output as i32;
-                }
+                // le spike ricevute nel giro ts sono state emesse al passo ts + 1
+                Ok(spike) => self.record(spike, self.ts as usize + 1)?,
                 Err(_) => return Err(SNNError::EmptyChannelError("Comunication ended".to_string())),
             }
         }
@@ -61,7 +91,7 @@ impl OutputMonitor {
         Ok(())
     }
 
-    pub fn run(mut self) -> JoinHandle<Vec<i32>> {
+    pub fn run(mut self) -> JoinHandle<OutputRecord> {
         // lancia un thread e restituisce un Join Handle, cambiare il return in Result e sostituire il break con un return di RecvError
         thread::spawn(move || {
             loop {
@@ -75,7 +105,7 @@ impl OutputMonitor {
                     }
                 }
             }
-            self.outputs
+            self.into_record()
         })
     }
 }
@@ -0,0 +1,258 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Barrier, Mutex};
+
+use super::{input_layer::InputLayer, neural_layer::NeuralLayer, neuron::Neuron, output::{OutputMonitor, OutputRecord}, spike::Spike, errors::SNNError};
+use super::clock_engine::{finish, firing_order, update, Fanout, Inbox};
+use super::event_engine::last_ticks;
+use super::synapse::Projection;
+use super::raster::{RasterTarget, SpikeRaster};
+
+// spike consegnata a un neurone di un altro worker: (passo, layer, neurone, sinapsi, spike)
+type Mail = (usize, usize, usize, usize, i8);
+
+/*
+Motore a passo fisso con un pool di worker di dimensione fissa (pari ai core disponibili), lanciati una sola volta con gli
+scoped thread di crossbeam. Ogni worker possiede una porzione contigua dei neuroni di ogni layer e la aggiorna a ogni passo; le
+spike emesse vengono consegnate scorrendo la connettività in uscita (vedi clock_engine::Fanout) delle sole sorgenti che hanno
+emesso: direttamente nelle code del worker se il neurone raggiunto è suo, altrimenti nella casella del worker che lo possiede,
+svuotata dopo la barriera successiva. Anche gli input sono divisi tra i worker. I worker si sincronizzano con una barriera dopo
+ogni gruppo di neuroni indipendenti: di norma un gruppo per layer, di più se il layer contiene sinapsi intra positive (lag 0)
+che impongono un ordine di aggiornamento. La temporizzazione è la stessa del motore clock-driven.
+*/
+pub fn run(input_layer: &InputLayer, layers: &mut [NeuralLayer], mut output_monitor: OutputMonitor, rasters: &mut [SpikeRaster]) -> Result<OutputRecord, SNNError> {
+    let orders = layers.iter().map(firing_order).collect::<Result<Vec<_>, _>>()?;
+    let last_ticks = last_ticks(input_layer, layers, &orders)?;
+    let horizon = last_ticks.iter().flatten().copied().max().unwrap_or(0);
+    let levels: Vec<Vec<usize>> = layers.iter().zip(&orders).map(|(l, order)| levels(l, order)).collect();
+    let n_levels: Vec<usize> = levels.iter().map(|l| l.iter().copied().max().map_or(0, |m| m + 1)).collect();
+    let fanout = Fanout::new(input_layer, layers);
+    let passive: Vec<Vec<bool>> = layers.iter().map(|l| l.neurons.iter().map(|n| n.synapses.iter().all(|s| s.is_passive())).collect()).collect();
+
+    let n_workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let barrier = Barrier::new(n_workers);
+    let mailboxes: Vec<Mutex<Vec<Mail>>> = (0..n_workers).map(|_| Mutex::new(vec![])).collect();
+
+    // porzione di ogni layer assegnata a ogni worker: (indice del primo neurone, neuroni)
+    let sizes: Vec<usize> = layers.iter().map(|l| l.neurons.len().div_ceil(n_workers).max(1)).collect();
+    let mut chunks: Vec<Vec<(usize, &mut [Neuron])>> = (0..n_workers).map(|_| vec![]).collect();
+    for (layer, &size) in layers.iter_mut().zip(&sizes) {
+        let mut layer_chunks = layer.neurons.chunks_mut(size);
+        for (w, worker_chunks) in chunks.iter_mut().enumerate() {
+            worker_chunks.push((w * size, layer_chunks.next().unwrap_or(&mut [])));
+        }
+    }
+    let input_size = input_layer.inputs.len().div_ceil(n_workers).max(1);
+
+    let last_layer = orders.len() - 1;
+    let monitored: Vec<bool> = (0..orders.len()).map(|l| rasters.iter().any(|r| r.target() == RasterTarget::Layer(l))).collect();
+    let ctx = Context { input_layer, last_ticks: &last_ticks, passive: &passive, fanout: &fanout, sizes: &sizes, mailboxes: &mailboxes, failed: AtomicBool::new(false) };
+    let res = crossbeam::scope(|s| {
+        let workers: Vec<_> = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(w, mut chunks)| {
+                let (ctx, barrier, levels, n_levels, orders, monitored) = (&ctx, &barrier, &levels, &n_levels, &orders, &monitored);
+                s.spawn(move |_| {
+                    // neuroni della porzione da aggiornare in ogni gruppo di ogni layer, nell'ordine di aggiornamento
+                    let schedule: Vec<Vec<Vec<usize>>> = chunks
+                        .iter()
+                        .enumerate()
+                        .map(|(l, (offset, neurons))| {
+                            let mut groups = vec![vec![]; n_levels[l]];
+                            for &j in orders[l].iter().filter(|j| (*offset..*offset + neurons.len()).contains(*j)) {
+                                groups[levels[l][j]].push(j);
+                            }
+                            groups
+                        })
+                        .collect();
+                    let mut worker = Worker {
+                        w,
+                        offsets: chunks.iter().map(|(offset, _)| *offset).collect(),
+                        inbox: [0, 1].map(|_| chunks.iter().map(|(_, neurons)| vec![vec![]; neurons.len()]).collect()),
+                        outgoing: vec![vec![]; ctx.mailboxes.len()],
+                    };
+                    let inputs = (w * input_size).min(ctx.input_layer.inputs.len())..((w + 1) * input_size).min(ctx.input_layer.inputs.len());
+                    // spike emesse dalla porzione dell'ultimo layer posseduta dal worker, con il passo di emissione
+                    let mut emitted = vec![];
+                    // spike dei layer osservati da un raster come (passo, layer, neurone)
+                    let mut fired = vec![];
+                    // primo errore o panic di aggiornamento: da quel momento nessun worker aggiorna più i neuroni, ma tutti
+                    // proseguono fino alla fine per non bloccare gli altri sulla barriera
+                    let mut error = None;
+                    for ts in 1..=horizon {
+                        for i in inputs.clone() {
+                            let spike = ctx.input_layer.inputs[i].spikes().get(ts - 1).copied().unwrap_or(0);
+                            if spike != 0 {
+                                for target in ctx.fanout.input_targets(i) {
+                                    worker.deliver(ctx, ts, target, spike);
+                                }
+                            }
+                        }
+                        worker.flush(ctx);
+                        barrier.wait();
+                        for (l, (offset, neurons)) in chunks.iter_mut().enumerate() {
+                            for group in &schedule[l] {
+                                worker.receive(ctx);
+                                for &j in group {
+                                    if ctx.failed.load(Ordering::Relaxed) {
+                                        break;
+                                    }
+                                    let neuron = &mut neurons[j - *offset];
+                                    let res = panic::catch_unwind(AssertUnwindSafe(|| worker.update(ctx, neuron, l, j, ts)));
+                                    let out_spike = match res.unwrap_or_else(|payload| Err(SNNError::from_panic("parallel worker", payload))) {
+                                        Ok(spike) => spike,
+                                        Err(e) => {
+                                            error.get_or_insert(e);
+                                            ctx.failed.store(true, Ordering::Relaxed);
+                                            0
+                                        }
+                                    };
+                                    if out_spike == 0 {
+                                        continue;
+                                    }
+                                    if l == last_layer {
+                                        emitted.push((Spike::new(out_spike, Some(j as i32)), ts));
+                                    }
+                                    if monitored[l] {
+                                        fired.push((ts, l, j));
+                                    }
+                                }
+                                worker.flush(ctx);
+                                barrier.wait();
+                            }
+                        }
+                    }
+                    match error {
+                        Some(e) => Err(e),
+                        None => Ok((emitted, fired)),
+                    }
+                })
+            })
+            .collect();
+        workers.into_iter().map(|w| w.join()).collect::<Vec<_>>()
+    });
+
+    let workers = match res {
+        Ok(workers) => workers,
+        Err(e) => return Err(SNNError::EngineError(format!("{:?}", e))),
+    };
+    let mut all_fired = vec![];
+    for worker in workers {
+        match worker {
+            Ok(result) => {
+                let (emitted, fired) = result?;
+                for (spike, ts) in emitted {
+                    output_monitor.record(spike, ts)?;
+                }
+                all_fired.extend(fired);
+            }
+            Err(e) => return Err(SNNError::EngineError(format!("{:?}", e))),
+        }
+    }
+    all_fired.sort();
+    for (ts, l, j) in all_fired {
+        for raster in rasters.iter_mut().filter(|r| r.target() == RasterTarget::Layer(l)) {
+            raster.record(ts, j);
+        }
+    }
+    Ok(output_monitor.into_record())
+}
+
+/*
+Dati condivisi in sola lettura dai worker, tranne le caselle delle spike destinate a ogni worker e l'indicatore di errore.
+*/
+struct Context<'a> {
+    input_layer: &'a InputLayer,
+    last_ticks: &'a [Vec<usize>],
+    passive: &'a [Vec<bool>],
+    fanout: &'a Fanout,
+    // neuroni di ogni layer posseduti da ciascun worker (l'ultimo può averne meno)
+    sizes: &'a [usize],
+    mailboxes: &'a [Mutex<Vec<Mail>>],
+    // true dopo il primo errore o panic di un worker, la simulazione prosegue senza aggiornare i neuroni
+    failed: AtomicBool,
+}
+
+/*
+Stato locale di un worker: le spike in arrivo ai suoi neuroni come (sinapsi, spike) nei passi pari (0) e dispari (1) e quelle
+destinate agli altri worker, in attesa di essere spostate nelle loro caselle prima della barriera.
+*/
+struct Worker {
+    w: usize,
+    offsets: Vec<usize>,
+    inbox: [Inbox; 2],
+    outgoing: Vec<Vec<Mail>>,
+}
+
+impl Worker {
+    fn update(&mut self, ctx: &Context, neuron: &mut Neuron, l: usize, j: usize, ts: usize) -> Result<i8, SNNError> {
+        // aggiorna il neurone j del layer l al passo ts e consegna la spike emessa
+        if ts > ctx.last_ticks[l][j] {
+            return Ok(0);
+        }
+        let local = j - self.offsets[l];
+        let mut events = std::mem::take(&mut self.inbox[ts % 2][l][local]);
+        let res = if ts == ctx.last_ticks[l][j] {
+            finish(neuron, l, j, ts, &mut events, ctx.input_layer, ctx.last_ticks);
+            Ok(0)
+        } else {
+            update(neuron, ts, &mut events, ctx.passive[l][j])
+        };
+        // il vettore svuotato viene riusato nei passi successivi
+        events.clear();
+        self.inbox[ts % 2][l][local] = events;
+        let out_spike = res?;
+        if out_spike != 0 {
+            for target in ctx.fanout.targets(l, j) {
+                self.deliver(ctx, ts, target, out_spike);
+            }
+        }
+        Ok(out_spike)
+    }
+
+    fn deliver(&mut self, ctx: &Context, ts: usize, (l, j, k, lag): (usize, usize, usize, usize), spike: i8) {
+        // consegna la spike emessa al passo ts alla sinapsi k del neurone j del layer l, se il neurone è ancora attivo quando
+        // la legge
+        if ts + lag > ctx.last_ticks[l][j] {
+            return;
+        }
+        let owner = j / ctx.sizes[l];
+        if owner == self.w {
+            self.inbox[(ts + lag) % 2][l][j - self.offsets[l]].push((k, spike));
+        } else {
+            self.outgoing[owner].push((ts + lag, l, j, k, spike));
+        }
+    }
+
+    fn flush(&mut self, ctx: &Context) {
+        // sposta le spike destinate agli altri worker nelle loro caselle
+        for (owner, mail) in self.outgoing.iter_mut().enumerate() {
+            if !mail.is_empty() {
+                ctx.mailboxes[owner].lock().unwrap().append(mail);
+            }
+        }
+    }
+
+    fn receive(&mut self, ctx: &Context) {
+        // sposta le spike ricevute dagli altri worker nelle code dei neuroni
+        let mut mailbox = ctx.mailboxes[self.w].lock().unwrap();
+        for (ts, l, j, k, spike) in mailbox.drain(..) {
+            self.inbox[ts % 2][l][j - self.offsets[l]].push((k, spike));
+        }
+    }
+}
+
+fn levels(layer: &NeuralLayer, order: &[usize]) -> Vec<usize> {
+    // gruppo di ogni neurone: i neuroni di uno stesso gruppo non dipendono l'uno dall'altro nello stesso passo e si possono
+    // aggiornare in parallelo, un neurone appartiene al gruppo successivo a quello delle sue sorgenti intra con lag 0
+    let mut levels = vec![0; layer.neurons.len()];
+    for &j in order {
+        for (k, synapse) in layer.neurons[j].synapses.iter().enumerate() {
+            if synapse.get_projection() == Projection::Intra && layer.neurons[j].lag(k) == 0 {
+                levels[j] = levels[j].max(levels[synapse.get_source()] + 1);
+            }
+        }
+    }
+    levels
+}
@@ -0,0 +1,103 @@
+use std::fs::File;
+
+use serde_json::json;
+
+use super::errors::SNNError;
+
+/*
+Sonda collegata a un singolo neurone: ogni every passi registra le variabili di stato del modello (potenziale di membrana ed
+eventuali variabili ausiliarie) e la soglia adattiva, se presente. Il campione del passo ts è preso dopo l'aggiornamento del
+neurone in quel passo; i passi partono da 1 come nell'output monitor.
+*/
+#[derive(Clone, Debug)]
+pub struct StateProbe {
+    every: usize,
+    // nomi delle variabili, letti al primo campione
+    names: Vec<&'static str>,
+    samples: Vec<(usize, Vec<f64>)>,
+    // sommato al passo dei campioni, permette di registrare più campioni di seguito (run_batch)
+    offset: usize,
+}
+
+impl StateProbe {
+    pub fn new(every: usize) -> Self {
+        Self { every: every.max(1), names: vec![], samples: vec![], offset: 0 }
+    }
+
+    pub fn is_due(&self, ts: usize) -> bool {
+        // true se al passo ts va registrato un campione
+        ts.is_multiple_of(self.every)
+    }
+
+    pub fn record(&mut self, ts: usize, variables: Vec<(&'static str, f64)>) {
+        if self.names.is_empty() {
+            self.names = variables.iter().map(|(name, _)| *name).collect();
+        }
+        self.samples.push((ts + self.offset, variables.into_iter().map(|(_, value)| value).collect()));
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.offset = 0;
+    }
+
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
+
+    pub fn samples(&self) -> &[(usize, Vec<f64>)] {
+        // campioni registrati come (passo, valori nello stesso ordine di names)
+        &self.samples
+    }
+
+    pub fn trace(&self, name: &str) -> Option<Vec<f64>> {
+        // valori di una singola variabile in tutti i campioni
+        let k = self.names.iter().position(|n| *n == name)?;
+        Some(self.samples.iter().map(|(_, values)| values[k]).collect())
+    }
+
+    pub fn write_csv(&self, path: &str) -> Result<(), SNNError> {
+        // una riga per campione, la prima colonna è il passo
+        let mut csv = String::from("ts");
+        for name in &self.names {
+            csv.push_str(&format!(",{name}"));
+        }
+        csv.push('\n');
+        for (ts, values) in &self.samples {
+            csv.push_str(&ts.to_string());
+            for value in values {
+                csv.push_str(&format!(",{value}"));
+            }
+            csv.push('\n');
+        }
+        match std::fs::write(path, csv) {
+            Err(e) => Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        // una serie per variabile, con i passi in "ts"
+        let mut traces = serde_json::Map::new();
+        traces.insert("ts".to_string(), json!(self.samples.iter().map(|(ts, _)| *ts).collect::<Vec<_>>()));
+        for name in &self.names {
+            traces.insert(name.to_string(), json!(self.trace(name)));
+        }
+        serde_json::Value::Object(traces)
+    }
+
+    pub fn write_json(&self, path: &str) -> Result<(), SNNError> {
+        let file = match File::create(path) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot create file {path}."))),
+            Ok(f) => f,
+        };
+        match serde_json::to_writer_pretty(file, &self.to_json()) {
+            Err(e) => Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+            Ok(_) => Ok(()),
+        }
+    }
+}
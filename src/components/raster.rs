@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use super::errors::SNNError;
+use super::input_layer::InputLayer;
+
+/*
+Layer osservato da un raster: l'input layer oppure uno dei layer neurali.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RasterTarget {
+    Input,
+    Layer(usize),
+}
+
+/*
+Monitor che registra ogni spike (passo, neurone) di un layer, in ordine di passo e a parità di passo di neurone. I passi partono
+da 1 come nell'output monitor. Con una capacità il raster conserva solo gli ultimi capacity eventi e conta quelli scartati,
+in modo da poter osservare simulazioni lunghe con memoria limitata.
+*/
+#[derive(Clone, Debug)]
+pub struct SpikeRaster {
+    target: RasterTarget,
+    capacity: Option<usize>,
+    events: VecDeque<(usize, usize)>,
+    // eventi scartati in modalità a memoria limitata
+    dropped: usize,
+    // sommato al passo degli eventi, permette di registrare più campioni di seguito (run_batch)
+    offset: usize,
+}
+
+impl SpikeRaster {
+    pub fn new(target: RasterTarget, capacity: Option<usize>) -> Self {
+        Self { target, capacity, events: VecDeque::new(), dropped: 0, offset: 0 }
+    }
+
+    pub fn target(&self) -> RasterTarget {
+        self.target
+    }
+
+    pub fn record(&mut self, ts: usize, neuron: usize) {
+        if self.capacity == Some(0) {
+            self.dropped += 1;
+            return;
+        }
+        if Some(self.events.len()) == self.capacity {
+            self.events.pop_front();
+            self.dropped += 1;
+        }
+        self.events.push_back((ts + self.offset, neuron));
+    }
+
+    pub fn record_inputs(&mut self, input_layer: &InputLayer) {
+        // registra tutte le spike emesse dagli input
+        let duration = input_layer.inputs.iter().map(|i| i.spikes().len()).max().unwrap_or(0);
+        for ts in 1..=duration {
+            for (n, input) in input_layer.inputs.iter().enumerate() {
+                if input.spikes().get(ts - 1).is_some_and(|s| *s != 0) {
+                    self.record(ts, n);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.dropped = 0;
+        self.offset = 0;
+    }
+
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        // eventi registrati come (passo, neurone)
+        self.events.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn write_csv(&self, path: &str) -> Result<(), SNNError> {
+        // una riga "passo,neurone" per evento, preceduta dall'intestazione
+        let mut csv = String::from("ts,neuron\n");
+        for (ts, neuron) in self.events() {
+            csv.push_str(&format!("{ts},{neuron}\n"));
+        }
+        match std::fs::write(path, csv) {
+            Err(e) => Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub fn write_binary(&self, path: &str) -> Result<(), SNNError> {
+        /*
+         * Formato binario compatto (little endian): numero di eventi e di eventi scartati come u64, seguiti dagli eventi, ognuno
+         * composto da passo e neurone (u32).
+         */
+        let file = match File::create(path) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot create file {path}."))),
+            Ok(f) => f,
+        };
+        let mut writer = BufWriter::new(file);
+        let mut bytes = Vec::with_capacity(16 + 8 * self.len());
+        bytes.extend((self.len() as u64).to_le_bytes());
+        bytes.extend((self.dropped as u64).to_le_bytes());
+        for (ts, neuron) in self.events() {
+            bytes.extend((ts as u32).to_le_bytes());
+            bytes.extend((neuron as u32).to_le_bytes());
+        }
+        match writer.write_all(&bytes).and_then(|_| writer.flush()) {
+            Err(e) => Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub fn read_binary(path: &str, target: RasterTarget) -> Result<Self, SNNError> {
+        // legge un raster scritto da write_binary
+        let file = match File::open(path) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot open file {path}."))),
+            Ok(f) => f,
+        };
+        let mut bytes = vec![];
+        if let Err(e) = BufReader::new(file).read_to_end(&mut bytes) {
+            return Err(SNNError::FileError(format!("File :{path}\nERROR:{e}")));
+        }
+        let word = |at: usize, size: usize| -> Option<u64> {
+            let mut buf = [0; 8];
+            buf[..size].copy_from_slice(bytes.get(at..at + size)?);
+            Some(u64::from_le_bytes(buf))
+        };
+        let bad_format = || SNNError::BadFormatError(format!("{path} is not a spike raster"));
+        let len = word(0, 8).ok_or_else(bad_format)? as usize;
+        let dropped = word(8, 8).ok_or_else(bad_format)? as usize;
+        if bytes.len() != 16 + 8 * len {
+            return Err(bad_format());
+        }
+        let mut raster = Self::new(target, None);
+        raster.dropped = dropped;
+        for e in 0..len {
+            let ts = word(16 + 8 * e, 4).ok_or_else(bad_format)? as usize;
+            let neuron = word(20 + 8 * e, 4).ok_or_else(bad_format)? as usize;
+            raster.events.push_back((ts, neuron));
+        }
+        Ok(raster)
+    }
+}
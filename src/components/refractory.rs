@@ -0,0 +1,77 @@
+use libm::exp;
+use serde::Deserialize;
+
+/*
+Parametri del periodo refrattario di un layer. Durante il periodo assoluto il neurone ignora gli ingressi, mentre durante quello
+relativo la soglia viene innalzata di increment dopo ogni spike e decade esponenzialmente con costante di tempo tau.
+*/
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct RefractoryConfig {
+    // numero di passi successivi a una spike in cui gli ingressi vengono ignorati
+    #[serde(default)]
+    pub absolute: u32,
+    pub relative: Option<RelativeRefractory>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct RelativeRefractory {
+    pub increment: f64,
+    pub tau: f64,
+}
+
+/*
+Stato refrattario del singolo neurone.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Refractory {
+    config: RefractoryConfig,
+    // passi di refrattarietà assoluta rimanenti
+    remaining: u32,
+    // innalzamento corrente della soglia
+    threshold_offset: f64,
+}
+
+impl Refractory {
+    pub fn new(config: RefractoryConfig) -> Self {
+        Self {
+            config,
+            remaining: 0,
+            threshold_offset: 0.0,
+        }
+    }
+
+    pub fn step(&mut self, dt: f64) -> bool {
+        // avanza di un passo, restituisce true se il neurone si trova nel periodo refrattario assoluto
+        if let Some(relative) = self.config.relative {
+            self.threshold_offset *= exp(-dt / relative.tau);
+        }
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            return true;
+        }
+        false
+    }
+
+    pub fn on_spike(&mut self) {
+        // il periodo refrattario comincia dal passo successivo alla spike
+        self.remaining = self.config.absolute;
+        if let Some(relative) = self.config.relative {
+            self.threshold_offset = relative.increment;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        // fine del periodo refrattario
+        self.remaining = 0;
+        self.threshold_offset = 0.0;
+    }
+
+    pub fn is_inert(&self) -> bool {
+        // true se il layer non ha periodo refrattario
+        self.config.absolute == 0 && self.config.relative.is_none()
+    }
+
+    pub fn threshold_offset(&self) -> f64 {
+        self.threshold_offset
+    }
+}
@@ -0,0 +1,32 @@
+/*
+Generatore pseudo-casuale SplitMix64: piccolo, veloce e riproducibile a partire da un seme, sufficiente per codificare gli
+ingressi e generare liste di guasti senza dipendere da librerie esterne. Non è adatto a usi crittografici.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        // valore uniforme in [0, 1) con 53 bit casuali
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        // intero uniforme in [0, n), n deve essere positivo
+        (self.next_f64() * n as f64) as usize
+    }
+}
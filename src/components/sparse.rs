@@ -0,0 +1,260 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use serde::Deserialize;
+
+use super::errors::SNNError;
+
+/*
+Valori che si possono memorizzare in una matrice sparsa: pesi (f64) e ritardi (u32). Nel formato binario tutti i valori sono
+scritti come f64.
+*/
+pub trait SparseValue: Copy + PartialEq + Default {
+    fn from_f64(x: f64) -> Option<Self>;
+    fn to_f64(self) -> f64;
+}
+
+impl SparseValue for f64 {
+    fn from_f64(x: f64) -> Option<Self> {
+        Some(x)
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl SparseValue for u32 {
+    fn from_f64(x: f64) -> Option<Self> {
+        // solo interi non negativi
+        if x.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&x) {
+            Some(x as u32)
+        } else {
+            None
+        }
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+/*
+Matrice sparsa in formato CSR (compressed sparse row): per ogni riga row_ptr indica l'intervallo di col_idx e values che contiene
+le colonne (in ordine crescente) e i valori degli elementi memorizzati. La memoria occupata è proporzionale al numero di
+elementi memorizzati e non alla dimensione della matrice.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Csr<T> {
+    rows: usize,
+    cols: usize,
+    row_ptr: Vec<usize>,
+    col_idx: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T: Copy> Csr<T> {
+    pub fn from_triplets(rows: usize, cols: usize, mut triplets: Vec<(usize, usize, T)>) -> Result<Self, SNNError> {
+        // costruisce la matrice da triplette (riga, colonna, valore) in qualsiasi ordine, errore se una tripletta è fuori
+        // dalle dimensioni dichiarate o se lo stesso elemento compare due volte
+        triplets.sort_by_key(|(i, j, _)| (*i, *j));
+        for (n, (i, j, _)) in triplets.iter().enumerate() {
+            if *i >= rows || *j >= cols {
+                return Err(SNNError::OutOfIndexError(format!("Element [{i}][{j}] is outside a {rows}x{cols} matrix")));
+            }
+            if n > 0 && (triplets[n - 1].0, triplets[n - 1].1) == (*i, *j) {
+                return Err(SNNError::BadFormatError(format!("Element [{i}][{j}] is specified twice")));
+            }
+        }
+        Ok(Self::from_sorted(rows, cols, triplets))
+    }
+
+    pub fn from_entries(rows: usize, cols: usize, mut entries: Vec<(usize, usize, T)>) -> Self {
+        // come from_triplets per indici già controllati, ma gli elementi ripetuti vengono memorizzati tutti nell'ordine in cui
+        // compaiono (usata per la connettività costruita dai motori di simulazione)
+        entries.sort_by_key(|(i, j, _)| (*i, *j));
+        Self::from_sorted(rows, cols, entries)
+    }
+
+    fn from_sorted(rows: usize, cols: usize, triplets: Vec<(usize, usize, T)>) -> Self {
+        let mut row_ptr = vec![0; rows + 1];
+        for (i, _, _) in &triplets {
+            row_ptr[i + 1] += 1;
+        }
+        for i in 0..rows {
+            row_ptr[i + 1] += row_ptr[i];
+        }
+        Self {
+            rows,
+            cols,
+            row_ptr,
+            col_idx: triplets.iter().map(|(_, j, _)| *j).collect(),
+            values: triplets.into_iter().map(|(_, _, v)| v).collect(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn nnz(&self) -> usize {
+        // numero di elementi memorizzati
+        self.values.len()
+    }
+
+    pub fn row(&self, i: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        // elementi memorizzati della riga i come (colonna, valore)
+        let range = self.row_ptr[i]..self.row_ptr[i + 1];
+        self.col_idx[range.clone()].iter().copied().zip(self.values[range].iter().copied())
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Option<T> {
+        // elemento (i, j), None se non è memorizzato
+        if i >= self.rows {
+            return None;
+        }
+        let range = self.row_ptr[i]..self.row_ptr[i + 1];
+        self.col_idx[range.clone()].binary_search(&j).ok().map(|k| self.values[range.start + k])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, T)> + '_ {
+        // tutti gli elementi memorizzati come triplette, per righe
+        (0..self.rows).flat_map(move |i| self.row(i).map(move |(j, v)| (i, j, v)))
+    }
+
+    pub fn map_values(&mut self, f: impl FnMut(&mut T)) {
+        // modifica i valori memorizzati senza cambiare la struttura, anche i valori che diventano 0 restano memorizzati
+        self.values.iter_mut().for_each(f);
+    }
+}
+
+impl<T: SparseValue> Csr<T> {
+    pub fn from_dense(matrix: &[Vec<T>], keep_zeros: bool) -> Self {
+        // converte una matrice densa, con keep_zeros = false gli elementi nulli non vengono memorizzati
+        let cols = matrix.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut row_ptr = Vec::with_capacity(matrix.len() + 1);
+        let (mut col_idx, mut values) = (vec![], vec![]);
+        row_ptr.push(0);
+        for row in matrix {
+            for (j, v) in row.iter().enumerate() {
+                if keep_zeros || *v != T::default() {
+                    col_idx.push(j);
+                    values.push(*v);
+                }
+            }
+            row_ptr.push(values.len());
+        }
+        Self { rows: matrix.len(), cols, row_ptr, col_idx, values }
+    }
+
+    pub fn from_binary(path: &str) -> Result<Self, SNNError> {
+        /*
+         * Legge una matrice dal formato binario (little endian): rows, cols e numero di triplette come u64, seguiti dalle
+         * triplette, ognuna composta da riga e colonna (u64) e valore (f64).
+         */
+        let file = match File::open(path) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot open file {path}."))),
+            Ok(f) => f,
+        };
+        // lunghezza del file, per controllare l'intestazione prima di allocare le triplette
+        let len = match file.metadata() {
+            Err(e) => return Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+            Ok(m) => m.len(),
+        };
+        let mut reader = BufReader::new(file);
+        let read_u64 = |reader: &mut BufReader<File>| -> Result<u64, SNNError> {
+            let mut buf = [0; 8];
+            match reader.read_exact(&mut buf) {
+                Err(e) => Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+                Ok(_) => Ok(u64::from_le_bytes(buf)),
+            }
+        };
+        let rows = read_u64(&mut reader)? as usize;
+        let cols = read_u64(&mut reader)? as usize;
+        let nnz = read_u64(&mut reader)?;
+        // intestazione e triplette occupano 24 byte ciascuna, un file corrotto non deve causare allocazioni arbitrarie
+        if nnz.checked_add(1).and_then(|n| n.checked_mul(24)) != Some(len) {
+            return Err(SNNError::BadFormatError(format!("File :{path}\nThe header declares {nnz} triplets but the file is {len} bytes long")));
+        }
+        let nnz = nnz as usize;
+        if rows.checked_mul(cols).is_none_or(|size| nnz > size) {
+            return Err(SNNError::BadFormatError(format!("File :{path}\nThe header declares {nnz} triplets for a {rows}x{cols} matrix")));
+        }
+        let mut triplets = Vec::with_capacity(nnz);
+        for _ in 0..nnz {
+            let i = read_u64(&mut reader)? as usize;
+            let j = read_u64(&mut reader)? as usize;
+            let x = f64::from_bits(read_u64(&mut reader)?);
+            match T::from_f64(x) {
+                None => return Err(SNNError::BadFormatError(format!("File :{path}\nInvalid value {x} at [{i}][{j}]"))),
+                Some(v) => triplets.push((i, j, v)),
+            }
+        }
+        Self::from_triplets(rows, cols, triplets)
+    }
+
+    pub fn write_binary(&self, path: &str) -> Result<(), SNNError> {
+        // scrive la matrice nel formato letto da from_binary
+        let file = match File::create(path) {
+            Err(_) => return Err(SNNError::FileError(format!("Cannot create file {path}."))),
+            Ok(f) => f,
+        };
+        let mut writer = BufWriter::new(file);
+        let mut words = vec![self.rows as u64, self.cols as u64, self.nnz() as u64];
+        for (i, j, v) in self.iter() {
+            words.extend([i as u64, j as u64, v.to_f64().to_bits()]);
+        }
+        for word in words {
+            if let Err(e) = writer.write_all(&word.to_le_bytes()) {
+                return Err(SNNError::FileError(format!("File :{path}\nERROR:{e}")));
+            }
+        }
+        match writer.flush() {
+            Err(e) => Err(SNNError::FileError(format!("File :{path}\nERROR:{e}"))),
+            Ok(_) => Ok(()),
+        }
+    }
+}
+
+/*
+Matrice come appare nel file JSON della rete: densa (vettore di righe) oppure sparsa, con le dimensioni e le triplette
+[riga, colonna, valore] scritte direttamente o lette da un file binario (vedi Csr::from_binary). In entrambi i casi gli indici
+hanno lo stesso significato della versione densa.
+*/
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum MatrixSpec<T> {
+    Dense(Vec<Vec<T>>),
+    Sparse {
+        rows: usize,
+        cols: usize,
+        #[serde(default)]
+        triplets: Vec<(usize, usize, T)>,
+        file: Option<String>,
+    },
+}
+
+impl<T: SparseValue> MatrixSpec<T> {
+    pub fn load(&self, keep_zeros: bool) -> Result<Csr<T>, SNNError> {
+        // keep_zeros vale solo per le matrici dense, delle matrici sparse si memorizzano tutte le triplette
+        match self {
+            MatrixSpec::Dense(matrix) => Ok(Csr::from_dense(matrix, keep_zeros)),
+            MatrixSpec::Sparse { rows, cols, triplets, file } => {
+                let mut csr = Csr::from_triplets(*rows, *cols, triplets.clone())?;
+                if let Some(path) = file {
+                    // le triplette del file si aggiungono a quelle scritte nel JSON
+                    let binary = Csr::from_binary(path)?;
+                    if (binary.rows(), binary.cols()) != (*rows, *cols) {
+                        return Err(SNNError::BadFormatError(format!("{path} is {}x{}, expected {rows}x{cols}", binary.rows(), binary.cols())));
+                    }
+                    csr = Csr::from_triplets(*rows, *cols, csr.iter().chain(binary.iter()).collect())?;
+                }
+                Ok(csr)
+            }
+        }
+    }
+}
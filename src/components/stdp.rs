@@ -0,0 +1,147 @@
+use libm::exp;
+use serde::Deserialize;
+
+use super::synapse::Projection;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StdpRule {
+    // coppie pre/post, tracce con costanti di tempo tau_plus e tau_minus
+    #[default]
+    Pair,
+    // regola a triplette di Pfister & Gerstner 2006, con le tracce lente tau_x (pre) e tau_y (post)
+    Triplet,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightBounds {
+    // il peso viene saturato in [w_min, w_max]
+    #[default]
+    Hard,
+    // moltiplicativo: il potenziamento è proporzionale a (w_max - w), la depressione a (w - w_min)
+    Soft,
+}
+
+/*
+Regola di apprendimento STDP di una proiezione, letta dal campo "stdp" del file JSON. La proiezione è identificata dal layer di
+destinazione e dal tipo: input (pesi input_weights[layer]) o intra (pesi intra_layer_weights[layer]).
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct StdpConfig {
+    pub layer: usize,
+    pub projection: Projection,
+    // permette di disattivare l'apprendimento senza rimuovere la regola
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rule: StdpRule,
+    #[serde(default = "learning_rate")]
+    pub learning_rate: f64,
+    // ampiezze e finestre temporali della componente a coppie
+    pub a_plus: f64,
+    pub a_minus: f64,
+    pub tau_plus: f64,
+    pub tau_minus: f64,
+    // ampiezze e finestre temporali della componente a triplette, ignorate dalla regola a coppie
+    #[serde(default)]
+    pub a3_plus: f64,
+    #[serde(default)]
+    pub a3_minus: f64,
+    #[serde(default = "slow_tau")]
+    pub tau_x: f64,
+    #[serde(default = "slow_tau")]
+    pub tau_y: f64,
+    pub w_min: f64,
+    pub w_max: f64,
+    #[serde(default)]
+    pub bounds: WeightBounds,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn learning_rate() -> f64 {
+    1.0
+}
+
+fn slow_tau() -> f64 {
+    // costanti di tempo del fit visual cortex all-to-all di Pfister & Gerstner
+    100.0
+}
+
+/*
+Tracce di eleggibilità di una singola sinapsi.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Stdp {
+    config: StdpConfig,
+    // tracce presinaptiche (r1 veloce, r2 lenta)
+    pre: f64,
+    pre_slow: f64,
+    // tracce postsinaptiche (o1 veloce, o2 lenta)
+    post: f64,
+    post_slow: f64,
+}
+
+impl Stdp {
+    pub fn new(config: StdpConfig) -> Self {
+        Self {
+            config,
+            pre: 0.0,
+            pre_slow: 0.0,
+            post: 0.0,
+            post_slow: 0.0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        // azzera le tracce, il peso appreso resta nella sinapsi
+        *self = Self::new(self.config);
+    }
+
+    pub fn step(&mut self, dt: f64) {
+        // decadimento delle tracce in un passo lungo dt
+        self.pre *= exp(-dt / self.config.tau_plus);
+        self.post *= exp(-dt / self.config.tau_minus);
+        if self.config.rule == StdpRule::Triplet {
+            self.pre_slow *= exp(-dt / self.config.tau_x);
+            self.post_slow *= exp(-dt / self.config.tau_y);
+        }
+    }
+
+    pub fn on_pre(&mut self, weight: f64) -> f64 {
+        // spike presinaptica: depressione proporzionale alla traccia post, poi aggiornamento delle tracce pre
+        let mut amplitude = self.config.a_minus;
+        if self.config.rule == StdpRule::Triplet {
+            amplitude += self.config.a3_minus * self.pre_slow;
+        }
+        let dw = -self.config.learning_rate * self.post * amplitude;
+        self.pre += 1.0;
+        self.pre_slow += 1.0;
+        self.bound(weight, dw)
+    }
+
+    pub fn on_post(&mut self, weight: f64) -> f64 {
+        // spike postsinaptica: potenziamento proporzionale alla traccia pre, poi aggiornamento delle tracce post
+        let mut amplitude = self.config.a_plus;
+        if self.config.rule == StdpRule::Triplet {
+            amplitude += self.config.a3_plus * self.post_slow;
+        }
+        let dw = self.config.learning_rate * self.pre * amplitude;
+        self.post += 1.0;
+        self.post_slow += 1.0;
+        self.bound(weight, dw)
+    }
+
+    fn bound(&self, weight: f64, dw: f64) -> f64 {
+        let (w_min, w_max) = (self.config.w_min, self.config.w_max);
+        let dw = match self.config.bounds {
+            WeightBounds::Hard => dw,
+            WeightBounds::Soft if dw > 0.0 => dw * (w_max - weight),
+            WeightBounds::Soft => dw * (weight - w_min),
+        };
+        (weight + dw).clamp(w_min, w_max)
+    }
+}
@@ -0,0 +1,65 @@
+use libm::exp;
+use serde::Deserialize;
+
+use super::synapse::Projection;
+
+/*
+Plasticità a breve termine di Tsodyks-Markram per un blocco di connessioni, letta dal campo "stp" del file JSON. Il blocco è
+identificato come per la STDP dal layer di destinazione e dal tipo di proiezione. Con tau_facil = 0 la sinapsi è solo
+depressiva, con tau_rec piccolo rispetto a tau_facil prevale la facilitazione.
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct StpConfig {
+    pub layer: usize,
+    pub projection: Projection,
+    // frazione di risorse utilizzata da una spike a riposo (U)
+    pub u: f64,
+    // costante di tempo del recupero delle risorse
+    pub tau_rec: f64,
+    // costante di tempo della facilitazione, 0 per disattivarla
+    #[serde(default)]
+    pub tau_facil: f64,
+}
+
+/*
+Stato della singola sinapsi: utilizzo corrente e frazione di risorse disponibili.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Stp {
+    config: StpConfig,
+    utilization: f64,
+    resources: f64,
+}
+
+impl Stp {
+    pub fn new(config: StpConfig) -> Self {
+        Self {
+            config,
+            utilization: config.u,
+            resources: 1.0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        // risorse piene e utilizzo a riposo
+        *self = Self::new(self.config);
+    }
+
+    pub fn step(&mut self, dt: f64) {
+        // recupero delle risorse verso 1 e decadimento dell'utilizzo verso U in un passo lungo dt
+        self.resources = 1.0 - (1.0 - self.resources) * exp(-dt / self.config.tau_rec);
+        if self.config.tau_facil > 0.0 {
+            self.utilization = self.config.u + (self.utilization - self.config.u) * exp(-dt / self.config.tau_facil);
+        }
+    }
+
+    pub fn on_pre(&mut self) -> f64 {
+        // spike presinaptica: restituisce la frazione del peso trasmessa (u * x) e consuma le risorse
+        if self.config.tau_facil > 0.0 {
+            self.utilization += self.config.u * (1.0 - self.utilization);
+        }
+        let efficacy = self.utilization * self.resources;
+        self.resources -= efficacy;
+        efficacy
+    }
+}
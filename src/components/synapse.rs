@@ -1,30 +1,130 @@
-use std::sync::mpsc::{Receiver};
+use std::collections::VecDeque;
+
+use serde::Deserialize;
+
+use super::{stdp::{Stdp, StdpConfig}, stp::{Stp, StpConfig}};
+use super::kinetics::{Kinetics, KineticsConfig};
+
+/*
+Tipo di proiezione a cui appartiene una sinapsi: input collega il layer precedente (o l'input layer per il primo layer) e
+corrisponde a input_weights, intra collega neuroni dello stesso layer e corrisponde a intra_layer_weights.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Projection {
+    Input,
+    Intra,
+}
 
-use super::{spike::Spike, errors::SNNError};
 /*
- Unità logica contenuta nei neuroni per ricevere le spike in ingresso, costituita da un receiver e da un peso associato alla connessione.
+ Unità logica contenuta nei neuroni per ricevere le spike in ingresso, costituita dal peso associato alla connessione e dallo stato
+ dei meccanismi sinaptici. Il trasporto delle spike (canali, vettori di spike) è compito del motore di simulazione, che consegna
+ alla sinapsi una spike per passo attraverso il metodo deliver.
 */
 pub struct Synapse {
     weight: f64,
-    rec: Receiver<Spike>,
-    
+    projection: Projection,
+    // indice del neurone (o dell'input) presinaptico all'interno del suo layer
+    source: usize,
+    // spike ricevute dal canale e non ancora consegnate al neurone, una per ogni passo di ritardo oltre il primo
+    delay_line: VecDeque<i8>,
+    // i meccanismi opzionali sono allocati solo se attivi, così una sinapsi passiva occupa poca memoria anche in reti con
+    // milioni di sinapsi
+    // tracce STDP, None se il peso è fisso
+    stdp: Option<Box<Stdp>>,
+    // plasticità a breve termine, None se ogni spike trasmette l'intero peso
+    stp: Option<Box<Stp>>,
+    // cinetica della corrente o conduttanza sinaptica, None per un salto istantaneo del potenziale
+    kinetics: Option<Box<Kinetics>>,
 }
 
 impl Synapse {
-    pub fn new(weight: f64, rec: Receiver<Spike>) -> Self {
-        Self { weight, rec}
+    pub fn new(weight: f64, projection: Projection, source: usize, delay: u32) -> Self {
+        // il trasporto introduce già un passo di ritardo, la coda contiene i restanti delay - 1
+        let delay_line = VecDeque::from(vec![0; delay.saturating_sub(1) as usize]);
+        Self { weight, projection, source, delay_line, stdp: None, stp: None, kinetics: None }
+    }
+
+    pub fn deliver(&mut self, spike: i8, v_mem: f64) -> f64 {
+        // riceve la spike presinaptica di un passo e restituisce l'ingresso pesato per il neurone, v_mem è il potenziale del
+        // neurone postsinaptico (usato dalle sinapsi a conduttanza)
+
+        // la spike ricevuta entra nella coda e ne esce quella emessa delay passi prima
+        self.delay_line.push_back(spike);
+        let output = self.delay_line.pop_front().unwrap();
+        let mut input = output as f64 * self.weight;
+        if let Some(stp) = self.stp.as_mut() {
+            // il peso efficace dipende dall'attività presinaptica recente
+            stp.step(1.0);
+            if output != 0 {
+                input *= stp.on_pre();
+            }
+        }
+        if let Some(kinetics) = self.kinetics.as_mut() {
+            // l'ingresso istantaneo alimenta la cinetica, al neurone arriva la corrente sinaptica
+            input = kinetics.current(input, self.weight, v_mem, 1.0);
+        }
+        if let Some(stdp) = self.stdp.as_mut() {
+            // il peso viene aggiornato dopo aver calcolato l'ingresso di questo passo
+            stdp.step(1.0);
+            if output != 0 {
+                self.weight = stdp.on_pre(self.weight);
+            }
+        }
+        input
     }
 
-    pub fn receive(&self) -> Result<f64, SNNError> {
-        // receive a single spike at a time
-        let msg = self.rec.recv();
-        match msg {
-            Ok(spike) => Ok(spike.output as f64 * self.weight),
-            Err(_) => Err(SNNError::EmptyChannelError("Call the connect before calling the receive method.".to_string())),
+    pub fn reset_state(&mut self) {
+        // svuota la coda dei ritardi e riporta a riposo i meccanismi sinaptici, il peso non cambia
+        self.delay_line.iter_mut().for_each(|spike| *spike = 0);
+        if let Some(stdp) = self.stdp.as_mut() {
+            stdp.reset();
         }
+        if let Some(stp) = self.stp.as_mut() {
+            stp.reset();
+        }
+        if let Some(kinetics) = self.kinetics.as_mut() {
+            kinetics.reset();
+        }
+    }
+
+    pub fn is_passive(&self) -> bool {
+        // true se la sinapsi trasmette solo spike * peso, senza stato che evolve a ogni passo
+        self.delay_line.is_empty() && self.stdp.is_none() && self.stp.is_none() && self.kinetics.is_none()
+    }
+
+    pub fn on_post_spike(&mut self) {
+        // il neurone postsinaptico ha emesso una spike
+        if let Some(stdp) = self.stdp.as_mut() {
+            self.weight = stdp.on_post(self.weight);
+        }
+    }
+
+    pub fn set_stdp(&mut self, config: StdpConfig) {
+        self.stdp = Some(Box::new(Stdp::new(config)));
+    }
+
+    pub fn set_stp(&mut self, config: StpConfig) {
+        self.stp = Some(Box::new(Stp::new(config)));
+    }
+
+    pub fn set_kinetics(&mut self, config: KineticsConfig) {
+        self.kinetics = Some(Box::new(Kinetics::new(config)));
     }
 
     pub fn get_weight(&self) -> f64{
         self.weight
     }
+
+    pub fn set_weight(&mut self, weight: f64) {
+        self.weight = weight;
+    }
+
+    pub fn get_projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn get_source(&self) -> usize {
+        self.source
+    }
 }
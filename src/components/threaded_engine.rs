@@ -0,0 +1,137 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use super::{input_layer::InputLayer, neural_layer::NeuralLayer, output::{OutputMonitor, OutputRecord}, spike::Spike, errors::SNNError};
+use super::synapse::Projection;
+use super::raster::{RasterTarget, SpikeRaster};
+
+/*
+Motore multi-thread: crea un channel per ogni sinapsi della rete, poi lancia un thread per ogni input, uno per ogni neurone e
+uno per l'output monitor. I neuroni tornano nei rispettivi layer al termine della simulazione per poterne leggere lo stato.
+A ogni passo ogni sinapsi trasporta una spike, anche nulla, quindi il costo è proporzionale al numero totale di sinapsi e non a
+quelle attive: la connettività CSR della rete serve qui solo a costruire i channel, gli altri motori la usano anche durante la
+simulazione.
+*/
+pub fn run(input_layer: &InputLayer, layers: &mut [NeuralLayer], mut output_monitor: OutputMonitor, rasters: &mut [SpikeRaster]) -> Result<OutputRecord, SNNError> {
+    // i sender verso il primo layer vengono aggiunti a una copia dell'input layer, così la rete si può simulare di nuovo
+    let mut input_layer = input_layer.clone();
+
+    // receivers[l][j] contiene un receiver per ogni sinapsi del neurone j del layer l, nello stesso ordine delle sinapsi;
+    // outputs[l][j] contiene i sender verso i neuroni collegati in uscita (o verso il monitor)
+    let mut receivers: Vec<Vec<Vec<Receiver<Spike>>>> = layers.iter().map(|l| l.neurons.iter().map(|_| vec![]).collect()).collect();
+    let mut outputs: Vec<Vec<Vec<Sender<Spike>>>> = layers.iter().map(|l| l.neurons.iter().map(|_| vec![]).collect()).collect();
+
+    for (l, layer) in layers.iter().enumerate() {
+        for (j, neuron) in layer.neurons.iter().enumerate() {
+            for synapse in &neuron.synapses {
+                let (tx, rx) = channel::<Spike>();
+                receivers[l][j].push(rx);
+                let i = synapse.get_source();
+                match synapse.get_projection() {
+                    Projection::Input if l == 0 => input_layer.add_sender_to(i, tx),
+                    Projection::Input => outputs[l - 1][i].push(tx),
+                    Projection::Intra => outputs[l][i].push(tx),
+                }
+            }
+        }
+    }
+
+    // assegna a ogni neurone dell'ultimo layer l'estremità di sender e aggiunge all'output monitor i receiver
+    let last_layer = outputs.len() - 1;
+    for out in outputs[last_layer].iter_mut() {
+        let (tx, rx) = channel::<Spike>();
+        out.push(tx);
+        output_monitor.add_receiver(rx);
+    }
+
+    // ogni raster di un layer riceve le spike di tutti i neuroni del layer su un thread dedicato
+    let mut tid_rasters = vec![];
+    for (r, raster) in rasters.iter().enumerate() {
+        if let RasterTarget::Layer(l) = raster.target() {
+            let mut raster_receivers = vec![];
+            for out in outputs[l].iter_mut() {
+                let (tx, rx) = channel::<Spike>();
+                out.push(tx);
+                raster_receivers.push(rx);
+            }
+            tid_rasters.push((r, thread::spawn(move || collect_raster(raster_receivers))));
+        }
+    }
+
+    // avvia tutti gli input e colleziona gli handler per fare join
+    let tid_input = input_layer.emit_spikes()?;
+    // lancia il metodo che riceve le spike di output dell'ultimo layer
+    let tid_output = output_monitor.run();
+
+    // lancia tutti i neuroni di ogni layer
+    let mut v = vec![];
+    for ((l, receivers), outputs) in layers.iter_mut().zip(receivers).zip(outputs) {
+        v.push(l.run_neurons(receivers, outputs));
+    }
+
+    // join dei vari thread: si attendono tutti, così i neuroni tornano nei layer anche se la simulazione fallisce, e si
+    // restituisce il primo errore. I neuroni vengono attesi per primi perché un loro errore fa fallire anche gli input che
+    // li alimentano
+    let mut error = None;
+    for (l, tids) in layers.iter_mut().zip(v) {
+        for tid in tids {
+            match tid.join() {
+                Ok((neuron, res)) => {
+                    l.add_neuron(neuron);
+                    match res {
+                        Ok(_) => println!("\t\t- neuron thread: OK"),
+                        Err(e) => {
+                            error.get_or_insert(e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    error.get_or_insert(SNNError::from_panic("neuron", e));
+                }
+            }
+        }
+    }
+
+    for (i, tid) in tid_input.into_iter().enumerate() {
+        match tid.join() {
+            Ok(_) => println!("\t\t- input thread[{}]: OK", i),
+            Err(e) => {
+                error.get_or_insert(SNNError::from_panic(&format!("input [{i}]"), e));
+            }
+        }
+    }
+
+    for (r, tid) in tid_rasters {
+        match tid.join() {
+            Ok(events) => events.into_iter().for_each(|(ts, j)| rasters[r].record(ts, j)),
+            Err(e) => {
+                error.get_or_insert(SNNError::from_panic("raster", e));
+            }
+        }
+    }
+
+    let record = match tid_output.join() {
+        Ok(record) => record,
+        Err(e) => return Err(error.unwrap_or_else(|| SNNError::from_panic("output monitor", e))),
+    };
+    match error {
+        Some(e) => Err(e),
+        None => Ok(record),
+    }
+}
+
+fn collect_raster(receivers: Vec<Receiver<Spike>>) -> Vec<(usize, usize)> {
+    // legge un giro di spike alla volta come l'output monitor, il giro ts contiene le spike emesse al passo ts + 1
+    let mut events = vec![];
+    let mut ts = 0;
+    loop {
+        for (j, receiver) in receivers.iter().enumerate() {
+            match receiver.recv() {
+                Ok(spike) if spike.output != 0 => events.push((ts + 1, j)),
+                Ok(_) => {}
+                Err(_) => return events,
+            }
+        }
+        ts += 1;
+    }
+}
@@ -0,0 +1,40 @@
+use libm::exp;
+use serde::Deserialize;
+
+/*
+Parametri della soglia adattiva (omeostatica) di un layer, come in Diehl & Cook 2015: ogni spike innalza la soglia del neurone
+di increment, l'innalzamento accumulato decade verso 0 con costante di tempo tau.
+*/
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct AdaptiveThresholdConfig {
+    pub increment: f64,
+    pub tau: f64,
+}
+
+/*
+Innalzamento della soglia del singolo neurone (theta).
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveThreshold {
+    config: AdaptiveThresholdConfig,
+    theta: f64,
+}
+
+impl AdaptiveThreshold {
+    pub fn new(config: AdaptiveThresholdConfig) -> Self {
+        Self { config, theta: 0.0 }
+    }
+
+    pub fn step(&mut self, dt: f64) {
+        // decadimento di theta in un passo lungo dt
+        self.theta *= exp(-dt / self.config.tau);
+    }
+
+    pub fn on_spike(&mut self) {
+        self.theta += self.config.increment;
+    }
+
+    pub fn theta(&self) -> f64 {
+        self.theta
+    }
+}
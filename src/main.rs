@@ -1,14 +1,19 @@
-mod components;
-use components::models::lif;
-use components::neural_network::NeuralNetwork;
+use snn::components::{engine::Engine, neural_network::NeuralNetwork};
 
 fn main() {
     println!("-------------------- START -------------------");
     println!("--- Creating neural network from test.json...");
-    let nn_res=NeuralNetwork::from_json("./test.json", lif);
+    let nn_res=NeuralNetwork::from_json("./test.json");
     match nn_res{
-        Ok(nn) => {
-            println!("{}", nn.to_string());
+        Ok(mut nn) => {
+            // motore di simulazione opzionale come primo argomento (threaded, clock_driven, event_driven o parallel)
+            if let Some(engine) = std::env::args().nth(1) {
+                match engine.parse::<Engine>() {
+                    Ok(engine) => nn.set_engine(engine),
+                    Err(e) => panic!("{:?}", e),
+                }
+            }
+            println!("{}", nn);
             println!("\t\tDONE.");
             println!("--- Starting simulation...");
             nn.run("output_file.txt");
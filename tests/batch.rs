@@ -0,0 +1,38 @@
+mod common;
+
+use snn::components::dataset::Sample;
+use snn::components::engine::Engine;
+use snn::components::kinetics::{Kernel, KineticsConfig};
+use snn::components::neural_network::NeuralNetwork;
+use snn::components::rng::Rng;
+use snn::components::stp::StpConfig;
+use snn::components::synapse::Projection;
+
+fn sample(seed: u64, n_inputs: usize, steps: usize) -> Sample {
+    let mut rng = Rng::new(seed);
+    Sample::new((0..n_inputs).map(|_| (0..steps).map(|_| i8::from(rng.next_f64() < 0.4)).collect()).collect(), None)
+}
+
+// rete con stato nei neuroni e nelle sinapsi, che senza reset passerebbe da un campione all'altro
+fn network(engine: Engine) -> NeuralNetwork {
+    let mut nn = common::scaled_network(7, 6, &[8, 4], 30, 1.5, common::lif);
+    nn.set_stp(StpConfig { layer: 0, projection: Projection::Input, u: 0.4, tau_rec: 50.0, tau_facil: 0.0 }).unwrap();
+    nn.set_kinetics(KineticsConfig { layer: 1, projection: Projection::Input, kernel: Kernel::Exponential { tau: 10.0 }, reversal: None })
+        .unwrap();
+    nn.set_engine(engine);
+    nn
+}
+
+#[test]
+fn batch_samples_do_not_depend_on_their_predecessors() {
+    let (a, b) = (sample(1, 6, 30), sample(2, 6, 30));
+    for engine in [Engine::Threaded, Engine::ClockDriven, Engine::EventDriven, Engine::Parallel] {
+        // nessun passo di riposo tra i campioni, lo stato di b sarebbe ancora presente all'inizio di a
+        let alone = network(engine).run_batch_records(std::slice::from_ref(&a), 30, 0).unwrap();
+        let after = network(engine).run_batch_records(&[b.clone(), a.clone(), a.clone()], 30, 0).unwrap();
+        assert!(alone[0].counts.iter().sum::<i32>() > 0, "{engine:?}");
+        assert_ne!(after[0], alone[0], "{engine:?}");
+        assert_eq!(after[1], alone[0], "{engine:?}");
+        assert_eq!(after[2], alone[0], "{engine:?}");
+    }
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use snn::components::campaign::{CampaignRunner, FaultSpace, Outcome};
+use snn::components::fault::{Fault, FaultComponent, FaultKind};
+use snn::components::fixed::{FixedLif, FixedPointConfig, Overflow, QFormat, Rounding};
+use snn::components::models::{boxed, NeuronDynamics};
+use snn::components::neural_network::NeuralNetwork;
+
+fn fixed(threshold: f64) -> Box<dyn NeuronDynamics> {
+    let config = FixedPointConfig {
+        membrane: QFormat::new(16, 16),
+        weight: QFormat::new(8, 8),
+        decay: QFormat::new(1, 15),
+        overflow: Overflow::Saturate,
+        rounding: Rounding::Nearest,
+        seed: 0,
+    };
+    boxed(FixedLif::new(config, threshold, -65.0, -66.0, 20.0, 0))
+}
+
+fn fixed_network() -> NeuralNetwork {
+    common::random_network(3, 4, &[5, 3], 30, fixed)
+}
+
+#[test]
+fn fault_space_offers_only_exposed_components() {
+    let space = FaultSpace::new(&mut common::random_network(3, 4, &[5, 3], 30, common::lif));
+    let registers = [FaultComponent::VThreshold, FaultComponent::VRest, FaultComponent::VReset, FaultComponent::VMem, FaultComponent::Weight];
+    assert_eq!(space.components, registers);
+
+    // il lif in virgola fissa non espone valori IEEE-754, restano solo i pesi
+    let mut space = FaultSpace::new(&mut fixed_network());
+    assert_eq!(space.components, [FaultComponent::Weight]);
+    space.components = registers.to_vec();
+    assert!(space.exhaustive().iter().all(|f| f.component == FaultComponent::Weight));
+    assert!(space.random(50, 1).iter().all(|f| f.component == FaultComponent::Weight));
+}
+
+#[test]
+fn campaign_on_fixed_point_network_has_no_fake_crashes() {
+    let mut space = FaultSpace::new(&mut fixed_network());
+    space.bits = vec![0, 20, 63];
+    let faults = space.random(30, 2);
+    assert_eq!(faults.len(), 30);
+    let report = CampaignRunner::new(|| Ok(fixed_network())).workers(2).run(&faults).unwrap();
+    assert!(report.results.iter().all(|r| r.outcome != Outcome::Crash), "{:?}", report.totals());
+}
+
+#[test]
+fn campaign_rejects_invalid_faults() {
+    let invalid = [
+        // il modello non espone la soglia
+        Fault::new(FaultComponent::VThreshold, 0, 0, None, 3, FaultKind::StuckAt1, 0),
+        // neurone inesistente
+        Fault::new(FaultComponent::Weight, 1, 9, Some(0), 3, FaultKind::StuckAt1, 0),
+        // bit-flip senza passo
+        Fault::new(FaultComponent::Weight, 0, 0, Some(0), 3, FaultKind::BitFlip, 0),
+    ];
+    let runner = CampaignRunner::new(|| Ok(fixed_network())).workers(2);
+    for fault in invalid {
+        assert!(runner.run(&[fault]).is_err(), "{fault:?}");
+    }
+}
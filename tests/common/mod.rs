@@ -0,0 +1,97 @@
+#![allow(dead_code)]
+
+use snn::components::fault::FaultComponent;
+use snn::components::input_layer::InputLayer;
+use snn::components::models::{boxed, Lif, NeuronDynamics, NeuronModel};
+use snn::components::neural_network::NeuralNetwork;
+use snn::components::output::OutputMonitor;
+use snn::components::rng::Rng;
+use snn::components::sparse::Csr;
+
+// rete casuale con layer di dimensione sizes, n_inputs ingressi e steps passi di simulazione, neuroni costruiti da model
+pub fn random_network<F>(seed: u64, n_inputs: usize, sizes: &[usize], steps: usize, model: F) -> NeuralNetwork
+where
+    F: Fn(f64) -> Box<dyn NeuronDynamics>,
+{
+    scaled_network(seed, n_inputs, sizes, steps, 1.0, model)
+}
+
+// come random_network, con tutti i pesi moltiplicati per gain
+pub fn scaled_network<F>(seed: u64, n_inputs: usize, sizes: &[usize], steps: usize, gain: f64, model: F) -> NeuralNetwork
+where
+    F: Fn(f64) -> Box<dyn NeuronDynamics>,
+{
+    let mut rng = Rng::new(seed);
+    let thresholds: Vec<Vec<f64>> = sizes.iter().map(|&n| (0..n).map(|_| -61.0 + 6.0 * rng.next_f64()).collect()).collect();
+    let mut nn = NeuralNetwork::new(thresholds, &[], |_, threshold| model(threshold));
+
+    let mut matrix = |rows: usize, cols: usize, low: f64, high: f64, diagonal: bool| {
+        (0..rows)
+            .map(|i| (0..cols).map(|j| if i == j && !diagonal { 0.0 } else { gain * (low + (high - low) * rng.next_f64()) }).collect())
+            .collect::<Vec<Vec<f64>>>()
+    };
+    let input_weights = matrix(sizes[0], n_inputs, 0.0, 3.0, true);
+    let intra: Vec<Vec<Vec<f64>>> = sizes.iter().map(|&n| matrix(n, n, -1.5, 0.0, false)).collect();
+    let forward: Vec<Vec<Vec<f64>>> = sizes.windows(2).map(|w| matrix(w[0], w[1], 0.0, 4.0, true)).collect();
+
+    for (l, weights) in intra.iter().enumerate() {
+        nn.connect(l, l, &Csr::from_dense(weights, false), None).unwrap();
+    }
+    for (l, weights) in forward.iter().enumerate() {
+        nn.connect(l, l + 1, &Csr::from_dense(weights, false), None).unwrap();
+    }
+    let trains: Vec<Vec<i8>> = (0..n_inputs).map(|_| (0..steps).map(|_| i8::from(rng.next_f64() < 0.3)).collect()).collect();
+    nn.connect_input_layer(InputLayer::from_spikes(trains), &Csr::from_dense(&input_weights, true), None).unwrap();
+    nn.connect_output(OutputMonitor::new(*sizes.last().unwrap()));
+    nn
+}
+
+// scrive in una cartella temporanea i treni di input (un input per riga) e il file JSON della rete con parameters, a cui
+// aggiunge il campo inputs, e restituisce il percorso del file JSON
+pub fn write_network(name: &str, mut parameters: serde_json::Value, trains: &[&str]) -> String {
+    let dir = std::env::temp_dir().join(format!("snn_{name}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    let inputs = dir.join("inputs.txt");
+    std::fs::write(&inputs, trains.join("\n")).unwrap();
+    parameters["inputs"] = serde_json::json!(inputs.to_str().unwrap());
+    let path = dir.join("network.json");
+    std::fs::write(&path, parameters.to_string()).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+pub fn lif(threshold: f64) -> Box<dyn NeuronDynamics> {
+    boxed(Lif::new(threshold, -65.0, -66.0, 20.0))
+}
+
+// modello che va in panic al terzo passo
+pub struct Explode;
+
+impl NeuronModel for Explode {
+    type State = usize;
+
+    fn init_state(&self) -> usize {
+        0
+    }
+
+    fn update(&self, steps: &mut usize, _inputs: &[f64], _dt: f64, _threshold_offset: f64) -> bool {
+        *steps += 1;
+        if *steps == 3 {
+            panic!("boom");
+        }
+        false
+    }
+
+    fn reset(&self, _steps: &mut usize) {}
+
+    fn v_mem(&self, _steps: &usize) -> f64 {
+        0.0
+    }
+
+    fn v_threshold(&self) -> f64 {
+        0.0
+    }
+
+    fn register<'a>(&'a mut self, _steps: &'a mut usize, _component: FaultComponent) -> Option<&'a mut f64> {
+        None
+    }
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use serde_json::json;
+use snn::components::compression::{compress_json, compress_parameters, CompressionConfig, Pruning, ScaleGranularity};
+use snn::components::neural_network::NeuralNetwork;
+
+fn dense_parameters() -> serde_json::Value {
+    // layer 0 con modulo massimo 2.1 nella matrice intra-layer, layer 1 con modulo massimo 4
+    json!({
+        "input_weights": [[[1.4, -0.7], [0.35, 0.0]], [[4.0, 1.0], [-3.1, 0.0]]],
+        "intra_layer_weights": [[[0.0, -2.1], [0.0, 0.0]], [[0.0, -0.5], [-0.5, 0.0]]],
+    })
+}
+
+fn values(matrix: &serde_json::Value) -> Vec<f64> {
+    matrix.as_array().unwrap().iter().flat_map(|row| row.as_array().unwrap().iter().map(|w| w.as_f64().unwrap())).collect()
+}
+
+fn close(a: &[f64], b: &[f64]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-12)
+}
+
+#[test]
+fn pruning_keeps_sparse_input_connections() {
+    // l'input 1 raggiunge solo il neurone 0, con un peso che viene potato
+    let mut parameters = json!({
+        "input_weights": [{ "rows": 2, "cols": 3, "triplets": [[0, 0, 5.0], [0, 1, 0.01], [1, 0, 5.0], [1, 2, 4.0]] }],
+        "intra_layer_weights": [{ "rows": 2, "cols": 2, "triplets": [[0, 1, -0.01], [1, 0, -2.0]] }],
+    });
+    let config = CompressionConfig { bits: None, scale: ScaleGranularity::Matrix, pruning: Some(Pruning::Magnitude { threshold: 0.1 }) };
+    let report = compress_parameters(&mut parameters, &config).unwrap();
+    assert_eq!(report.matrices[0].pruned, 1);
+    assert_eq!(parameters["input_weights"][0]["triplets"], json!([[0, 0, 5.0], [0, 1, 0.0], [1, 0, 5.0], [1, 2, 4.0]]));
+    assert_eq!(parameters["intra_layer_weights"][0]["triplets"], json!([[1, 0, -2.0]]));
+}
+
+#[test]
+fn quantization_uses_one_scale_per_layer() {
+    let mut parameters = dense_parameters();
+    let config = CompressionConfig { bits: Some(4), scale: ScaleGranularity::Layer, pruning: None };
+    let report = compress_parameters(&mut parameters, &config).unwrap();
+    // 4 bit: livelli interi in [-7, 7], scala pari al massimo modulo del layer diviso 7
+    let scales: Vec<f64> = report.matrices.iter().map(|m| m.scale.unwrap()).collect();
+    assert!(close(&scales, &[0.3, 4.0 / 7.0, 0.3, 4.0 / 7.0]), "{scales:?}");
+    assert_eq!(report.matrices.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["input_weights[0]", "input_weights[1]", "intra_layer_weights[0]", "intra_layer_weights[1]"]);
+
+    // 1.4 -> 5 * 0.3, -0.7 -> -2 * 0.3, 0.35 -> 1 * 0.3
+    let input = values(&parameters["input_weights"][0]);
+    assert!(close(&input, &[1.5, -0.6, 0.3, 0.0]), "{input:?}");
+    let error = &report.matrices[0].error;
+    assert_eq!(error.count, 3);
+    assert!((error.rmse - ((0.01 + 0.01 + 0.0025) / 3.0f64).sqrt()).abs() < 1e-12, "{}", error.rmse);
+    assert!((error.max_abs - 0.1).abs() < 1e-12);
+    assert!(close(&values(&parameters["intra_layer_weights"][0]), &[0.0, -2.1, 0.0, 0.0]));
+
+    // ogni peso è un multiplo intero della scala, entro ±7 * scala
+    for (m, summary) in report.matrices.iter().enumerate() {
+        let field = if m < 2 { "input_weights" } else { "intra_layer_weights" };
+        let scale = summary.scale.unwrap();
+        for w in values(&parameters[field][m % 2]) {
+            assert!(w.abs() <= 7.0 * scale + 1e-12, "{field} {w}");
+            assert!(((w / scale).round() - w / scale).abs() < 1e-9, "{field} {w}");
+        }
+    }
+}
+
+#[test]
+fn quantization_can_use_one_scale_per_matrix() {
+    let mut parameters = dense_parameters();
+    let config = CompressionConfig { bits: Some(4), scale: ScaleGranularity::Matrix, pruning: None };
+    let report = compress_parameters(&mut parameters, &config).unwrap();
+    let scales: Vec<f64> = report.matrices.iter().map(|m| m.scale.unwrap()).collect();
+    assert!(close(&scales, &[0.2, 4.0 / 7.0, 0.3, 0.5 / 7.0]), "{scales:?}");
+    // 1.4 -> 7 * 0.2, -0.7 -> -4 * 0.2 (i casi a metà si allontanano dallo zero), 0.35 -> 2 * 0.2
+    assert!(close(&values(&parameters["input_weights"][0]), &[1.4, -0.8, 0.4, 0.0]));
+}
+
+#[test]
+fn percentage_pruning_removes_ties_together() {
+    let pruned = |fraction: f64| {
+        let mut parameters = json!({
+            "input_weights": [[[0.5, -0.5, 3.0], [1.0, 0.5, -2.0]]],
+            "intra_layer_weights": [[[0.0, 0.0], [0.0, 0.0]]],
+        });
+        let config = CompressionConfig { bits: None, scale: ScaleGranularity::Matrix, pruning: Some(Pruning::Percentage { fraction }) };
+        let report = compress_parameters(&mut parameters, &config).unwrap();
+        (report.matrices[0].pruned, values(&parameters["input_weights"][0]))
+    };
+    assert_eq!(pruned(0.0), (0, vec![0.5, -0.5, 3.0, 1.0, 0.5, -2.0]));
+    // il 20% di 6 pesi è uno solo, ma i tre pesi di modulo 0.5 sono a pari merito
+    assert_eq!(pruned(0.2), (3, vec![0.0, 0.0, 3.0, 1.0, 0.0, -2.0]));
+    assert_eq!(pruned(0.6), (4, vec![0.0, 0.0, 3.0, 0.0, 0.0, -2.0]));
+    assert_eq!(pruned(1.0), (6, vec![0.0; 6]));
+}
+
+#[test]
+fn compressed_network_loads_and_keeps_dense_matrices_dense() {
+    let path = common::write_network(
+        "compression_load",
+        json!({
+            "tau": 20, "rest_potential": -65, "reset_potential": -66, "thresholds": [[-60.0, -61.0], [-60.0]],
+            "input_weights": [[[6.0, 0.2, 3.3], [2.9, 5.1, 0.1]], [[4.2], [3.7]]],
+            "intra_layer_weights": [[[0.0, -1.3], [-0.9, 0.0]], { "rows": 1, "cols": 1, "triplets": [] }],
+        }),
+        &["1011010011", "0110101101", "1100110110"],
+    );
+    let output = path.replace("network.json", "compressed.json");
+    let config = CompressionConfig { bits: Some(3), scale: ScaleGranularity::Layer, pruning: Some(Pruning::Magnitude { threshold: 0.5 }) };
+    let report = compress_json(&path, &output, &config).unwrap();
+    assert_eq!(report.matrices[0].pruned, 2);
+
+    let compressed: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    // 3 bit: livelli in [-3, 3] con scala 6 / 3 nel layer 0
+    assert_eq!(values(&compressed["input_weights"][0]), [6.0, 0.0, 4.0, 2.0, 6.0, 0.0]);
+    assert!(compressed["intra_layer_weights"][0].is_array());
+    assert!(compressed["intra_layer_weights"][1].is_object());
+
+    let mut nn = NeuralNetwork::from_json(&output).unwrap();
+    assert_eq!(nn.shape().iter().map(|l| l.len()).collect::<Vec<_>>(), [2, 1]);
+    assert_eq!(nn.simulate().unwrap().len(), 1);
+}
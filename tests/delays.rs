@@ -0,0 +1,38 @@
+mod common;
+
+use serde_json::json;
+use snn::components::engine::Engine;
+use snn::components::neural_network::NeuralNetwork;
+use snn::components::raster::RasterTarget;
+
+// passi delle spike dei due layer di una catena input -> neurone -> neurone, con i ritardi indicati
+fn spike_times(name: &str, engine: Engine, input_delay: u32, forward_delay: u32) -> (Vec<usize>, Vec<usize>) {
+    let path = common::write_network(
+        &format!("{name}_{engine:?}"),
+        json!({
+            "tau": 20, "rest_potential": -65, "reset_potential": -66, "thresholds": [[-60.0], [-60.0]],
+            "input_weights": [[[10.0]], [[10.0]]], "intra_layer_weights": [[[0.0]], [[0.0]]],
+            "input_delays": [[[input_delay]], [[forward_delay]]],
+        }),
+        &["0100000000000"],
+    );
+    let mut nn = NeuralNetwork::from_json(&path).unwrap();
+    nn.set_engine(engine);
+    let first = nn.add_raster(RasterTarget::Layer(0), None).unwrap();
+    let second = nn.add_raster(RasterTarget::Layer(1), None).unwrap();
+    nn.simulate().unwrap();
+    let times = |id| nn.raster(id).unwrap().events().map(|(ts, _)| ts).collect();
+    (times(first), times(second))
+}
+
+#[test]
+fn spikes_arrive_after_their_delay_on_every_engine() {
+    for engine in [Engine::Threaded, Engine::ClockDriven, Engine::EventDriven, Engine::Parallel] {
+        // il ritardo di default (1) è il trasporto nello stesso passo della rete originale: la spike di input al passo 2
+        // raggiunge il primo layer al passo 2 + (d - 1) e il secondo dopo altri e - 1 passi
+        for (d, e) in [(1, 1), (3, 1), (1, 5), (4, 2)] {
+            let expected = (vec![1 + d as usize], vec![(d + e) as usize]);
+            assert_eq!(spike_times("delays", engine, d, e), expected, "{engine:?} {d} {e}");
+        }
+    }
+}
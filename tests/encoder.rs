@@ -0,0 +1,73 @@
+use snn::components::encoder::{DeltaEncoder, Encoding, LatencyEncoder, LatencyScale, RateEncoder, RateProcess};
+use snn::components::rng::Rng;
+
+#[test]
+fn log_latency_without_threshold_is_monotonic() {
+    let encoder = LatencyEncoder::new(LatencyScale::Log, 20);
+    let latencies: Vec<usize> = [1.0, 0.8, 0.5, 0.2, 0.05, 0.001].iter().map(|x| encoder.latency(*x).unwrap()).collect();
+    assert_eq!(latencies[0], 0);
+    assert!(latencies.windows(2).all(|w| w[0] <= w[1]), "{latencies:?}");
+    // i valori piccoli vengono saturati all'ultimo passo
+    assert_eq!(*latencies.last().unwrap(), 19);
+    assert_eq!(encoder.latency(0.0), None);
+}
+
+#[test]
+fn log_latency_with_threshold_follows_rc_charge() {
+    let encoder = LatencyEncoder { threshold: 0.2, ..LatencyEncoder::new(LatencyScale::Log, 20) };
+    // t = 5 * ln(0.5 / 0.3)
+    assert_eq!(encoder.latency(0.5), Some(3));
+    assert_eq!(encoder.latency(0.2), None);
+}
+
+#[test]
+fn delta_encoder_ignores_constant_signals() {
+    let encoder = DeltaEncoder::new(0.0);
+    assert_eq!(encoder.encode(&[vec![0.5; 6]]), vec![vec![0; 6], vec![0; 6]]);
+    // con soglia nulla ogni variazione produce una spike
+    assert_eq!(encoder.encode(&[vec![0.5, 0.6, 0.6, 0.4]]), vec![vec![0, 1, 0, 0], vec![0, 0, 0, 1]]);
+}
+
+#[test]
+fn rate_encoding_is_reproducible_with_the_same_seed() {
+    for process in [RateProcess::Poisson, RateProcess::Bernoulli] {
+        let encoding = |seed| Encoding::Rate(RateEncoder::new(process, 100, 300.0, seed));
+        let samples = [vec![0.2, 0.9, 0.5], vec![1.0, 0.0, 0.7]];
+        let trains = encoding(42).encode_all(&samples);
+        assert_eq!(trains, encoding(42).encode_all(&samples), "{process:?}");
+        assert_ne!(trains, encoding(43).encode_all(&samples), "{process:?}");
+        assert_eq!(trains[0].len(), 3);
+        assert!(trains[0].iter().all(|t| t.len() == 100));
+    }
+}
+
+#[test]
+fn rate_tracks_the_input_value() {
+    let values = [0.0, 0.1, 0.25, 0.5, 1.0];
+    for process in [RateProcess::Poisson, RateProcess::Bernoulli] {
+        // 200 Hz con passi di 1 ms: al massimo il 20% dei passi ha una spike
+        let encoder = RateEncoder::new(process, 20_000, 200.0, 7);
+        let trains = encoder.encode(&values, &mut Rng::new(encoder.seed));
+        let rates: Vec<f64> = trains.iter().map(|t| t.iter().map(|s| *s as f64).sum::<f64>() / 20_000.0).collect();
+        for (value, rate) in values.iter().zip(&rates) {
+            let expected = match process {
+                RateProcess::Poisson => 1.0 - (-0.2 * value).exp(),
+                RateProcess::Bernoulli => 0.2 * value,
+            };
+            assert!((encoder.probability(*value) - expected).abs() < 1e-12);
+            assert!((rate - expected).abs() < 0.01, "{process:?} {value} {rate}");
+        }
+        assert_eq!(rates[0], 0.0);
+        assert!(rates.windows(2).all(|w| w[0] < w[1]), "{process:?} {rates:?}");
+    }
+}
+
+#[test]
+fn rate_encoder_normalizes_to_its_range() {
+    let encoder = RateEncoder { min: 0.0, max: 255.0, ..RateEncoder::new(RateProcess::Bernoulli, 10, 500.0, 0) };
+    assert_eq!(encoder.probability(255.0), 0.5);
+    assert!((encoder.probability(51.0) - 0.1).abs() < 1e-12);
+    // i valori fuori dall'intervallo vengono saturati
+    assert_eq!(encoder.probability(-10.0), 0.0);
+    assert_eq!(encoder.probability(1000.0), 0.5);
+}
@@ -0,0 +1,100 @@
+use std::cell::Cell;
+
+mod common;
+
+use snn::components::engine::Engine;
+use snn::components::errors::SNNError;
+use snn::components::fault::{Fault, FaultComponent, FaultKind};
+use snn::components::models::{boxed, Izhikevich, NeuronDynamics};
+use snn::components::output::OutputRecord;
+
+fn records<F: Fn(f64) -> Box<dyn NeuronDynamics> + Copy>(seed: u64, sizes: &[usize], gain: f64, model: F) -> Vec<(Engine, OutputRecord)> {
+    faulty_records(seed, sizes, gain, model, &[])
+}
+
+fn faulty_records<F: Fn(f64) -> Box<dyn NeuronDynamics> + Copy>(seed: u64, sizes: &[usize], gain: f64, model: F, faults: &[Fault]) -> Vec<(Engine, OutputRecord)> {
+    [Engine::Threaded, Engine::ClockDriven, Engine::EventDriven, Engine::Parallel]
+        .into_iter()
+        .map(|engine| {
+            let mut nn = common::scaled_network(seed, 6, sizes, 60, gain, model);
+            for fault in faults {
+                nn.add_fault(*fault).unwrap();
+            }
+            nn.set_engine(engine);
+            (engine, nn.simulate_record().unwrap())
+        })
+        .collect()
+}
+
+fn assert_same(records: &[(Engine, OutputRecord)]) {
+    let (_, reference) = &records[0];
+    assert!(reference.counts.iter().any(|c| *c > 0), "the network never fires: {reference:?}");
+    for (engine, record) in &records[1..] {
+        assert_eq!(record.counts, reference.counts, "spike counts of {engine:?}");
+        assert_eq!(record.first_spikes, reference.first_spikes, "first spike times of {engine:?}");
+    }
+}
+
+#[test]
+fn engines_match_on_lif_networks() {
+    for seed in 0..20 {
+        assert_same(&records(seed, &[8, 5, 3], 1.0, common::lif));
+    }
+}
+
+#[test]
+fn engines_match_on_deep_networks() {
+    for seed in 20..25 {
+        assert_same(&records(seed, &[10, 8, 6, 4, 3], 1.0, common::lif));
+    }
+}
+
+#[test]
+fn engines_match_on_izhikevich_networks() {
+    // neuroni non event-driven: il motore a eventi li aggiorna a ogni passo
+    for seed in 0..5 {
+        assert_same(&records(seed, &[6, 4], 10.0, |_| boxed(Izhikevich::new(0.02, 0.2, -65.0, 8.0))));
+    }
+}
+
+#[test]
+fn engines_match_with_faults() {
+    // i guasti sul bit di segno cambiano il lag delle sinapsi: una sinapsi intra diventa positiva e impone un ordine di
+    // aggiornamento, una sinapsi di input e una in avanti diventano negative
+    let faults = [
+        Fault::new(FaultComponent::Weight, 0, 1, Some(0), 63, FaultKind::StuckAt0, 0),
+        Fault::new(FaultComponent::Weight, 0, 2, Some(8), 63, FaultKind::StuckAt1, 0),
+        Fault::new(FaultComponent::Weight, 1, 0, Some(5), 63, FaultKind::StuckAt1, 0),
+        Fault::new(FaultComponent::VThreshold, 1, 3, None, 52, FaultKind::BitFlip, 20),
+    ];
+    for seed in 0..10 {
+        assert_same(&faulty_records(seed, &[8, 5, 3], 1.0, common::lif, &faults));
+    }
+}
+
+#[test]
+fn engines_can_simulate_again() {
+    for engine in [Engine::Threaded, Engine::ClockDriven, Engine::EventDriven, Engine::Parallel] {
+        let mut nn = common::random_network(7, 6, &[8, 3], 60, common::lif);
+        nn.set_engine(engine);
+        let first = nn.simulate_record().unwrap();
+        nn.reset_state();
+        assert_eq!(first, nn.simulate_record().unwrap(), "{engine:?}");
+    }
+}
+
+#[test]
+fn parallel_engine_reports_neuron_panics() {
+    // un solo neurone (il quinto del primo layer) va in panic, gli altri worker non devono restare bloccati sulla barriera
+    let built = Cell::new(0);
+    let mut nn = common::random_network(4, 6, &[16, 8], 40, |threshold| {
+        built.set(built.get() + 1);
+        if built.get() == 5 { boxed(common::Explode) } else { common::lif(threshold) }
+    });
+    nn.set_engine(Engine::Parallel);
+    match nn.simulate() {
+        Err(SNNError::EngineError(message)) => assert!(message.contains("boom"), "{message}"),
+        res => panic!("expected an engine error, got {res:?}"),
+    }
+    assert_eq!(nn.shape().iter().map(Vec::len).collect::<Vec<_>>(), [16, 8]);
+}
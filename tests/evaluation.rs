@@ -0,0 +1,99 @@
+use snn::components::evaluation::{CountDecoder, Decoding, Evaluation, FirstSpikeDecoder, NoSpike, TieBreak};
+use snn::components::input_layer::InputLayer;
+use snn::components::models::{boxed, Lif};
+use snn::components::neural_network::NeuralNetwork;
+use snn::components::output::OutputMonitor;
+use snn::components::sparse::Csr;
+
+// conteggi di sei campioni su tre neuroni di output, con pari merito tra 0 e 1 (campione 1) e tra 1 e 2 (campione 4) e un
+// campione senza spike
+const COUNTS: [[i32; 3]; 6] = [[3, 1, 0], [2, 2, 1], [0, 0, 0], [0, 1, 4], [1, 5, 5], [4, 0, 1]];
+const LABELS: [usize; 6] = [0, 1, 2, 2, 1, 1];
+
+fn evaluate(tie_break: TieBreak, no_spike: NoSpike) -> Evaluation {
+    let counts: Vec<Vec<i32>> = COUNTS.iter().map(|c| c.to_vec()).collect();
+    Evaluation::from_counts(&counts, &LABELS, &CountDecoder::new(tie_break, no_spike)).unwrap()
+}
+
+#[test]
+fn count_decoder_breaks_ties() {
+    let decode = |tie_break, no_spike| COUNTS.iter().map(|c| CountDecoder::new(tie_break, no_spike).decode(c)).collect::<Vec<_>>();
+    assert_eq!(decode(TieBreak::Lowest, NoSpike::Reject), [Some(0), Some(0), None, Some(2), Some(1), Some(0)]);
+    assert_eq!(decode(TieBreak::Highest, NoSpike::Reject), [Some(0), Some(1), None, Some(2), Some(2), Some(0)]);
+    assert_eq!(decode(TieBreak::Reject, NoSpike::Reject), [Some(0), None, None, Some(2), None, Some(0)]);
+    // senza spike decide la classe fissa oppure la regola di pari merito tra tutti i neuroni
+    assert_eq!(decode(TieBreak::Lowest, NoSpike::Class(1))[2], Some(1));
+    assert_eq!(decode(TieBreak::Highest, NoSpike::TieBreak)[2], Some(2));
+}
+
+#[test]
+fn confusion_matrix_and_accuracy_match_hand_computation() {
+    let evaluation = evaluate(TieBreak::Lowest, NoSpike::Reject);
+    assert_eq!(evaluation.confusion_matrix(), [vec![1, 0, 0], vec![2, 1, 0], vec![0, 0, 1]]);
+    assert_eq!((evaluation.samples(), evaluation.rejected()), (6, 1));
+    // il campione rifiutato conta come errore
+    assert_eq!(evaluation.accuracy(), 0.5);
+    assert_eq!(evaluation.precision(0), Some(1.0 / 3.0));
+    assert_eq!(evaluation.recall(1), Some(1.0 / 3.0));
+    assert_eq!((evaluation.precision(2), evaluation.recall(2)), (Some(1.0), Some(0.5)));
+    assert_eq!(evaluation.support(2), 2);
+
+    let evaluation = evaluate(TieBreak::Highest, NoSpike::Reject);
+    assert_eq!(evaluation.confusion_matrix(), [vec![1, 0, 0], vec![1, 1, 1], vec![0, 0, 1]]);
+    assert_eq!(evaluation.accuracy(), 0.5);
+
+    let evaluation = evaluate(TieBreak::Reject, NoSpike::Reject);
+    assert_eq!(evaluation.confusion_matrix(), [vec![1, 0, 0], vec![1, 0, 0], vec![0, 0, 1]]);
+    assert_eq!((evaluation.rejected(), evaluation.accuracy()), (3, 2.0 / 6.0));
+    assert_eq!(evaluation.precision(1), None);
+
+    let evaluation = evaluate(TieBreak::Highest, NoSpike::TieBreak);
+    assert_eq!((evaluation.rejected(), evaluation.accuracy()), (0, 4.0 / 6.0));
+}
+
+#[test]
+fn evaluation_keeps_classes_that_never_appear() {
+    // quattro neuroni di output ma nessun campione della classe 3
+    let evaluation = Evaluation::from_counts(&[vec![0, 2, 1, 0]], &[1], &CountDecoder::default()).unwrap();
+    assert_eq!(evaluation.n_classes(), 4);
+    assert_eq!((evaluation.support(3), evaluation.recall(3), evaluation.accuracy()), (0, None, 1.0));
+    assert!(Evaluation::from_counts(&[vec![1, 0]], &[0, 1], &CountDecoder::default()).is_err());
+}
+
+#[test]
+fn first_spike_decoder_picks_the_earliest_neuron() {
+    let decoder = FirstSpikeDecoder::new(TieBreak::Lowest, NoSpike::Reject);
+    assert_eq!(decoder.decode(&[Some(7), None, Some(3), Some(5)]), Some(2));
+    // pari merito al passo 3 tra i neuroni 1 e 3
+    let tied = [Some(7), Some(3), None, Some(3)];
+    assert_eq!(decoder.decode(&tied), Some(1));
+    assert_eq!(FirstSpikeDecoder::new(TieBreak::Highest, NoSpike::Reject).decode(&tied), Some(3));
+    assert_eq!(FirstSpikeDecoder::new(TieBreak::Reject, NoSpike::Reject).decode(&tied), None);
+    assert_eq!(decoder.decode(&[None, None]), None);
+    assert_eq!(FirstSpikeDecoder::new(TieBreak::Highest, NoSpike::TieBreak).decode(&[None, None]), Some(1));
+}
+
+#[test]
+fn rank_order_lists_neurons_by_first_spike() {
+    let first_spikes = [Some(7), Some(3), None, Some(3), Some(1)];
+    assert_eq!(FirstSpikeDecoder::new(TieBreak::Lowest, NoSpike::Reject).rank_order(&first_spikes), [4, 1, 3, 0]);
+    assert_eq!(FirstSpikeDecoder::new(TieBreak::Highest, NoSpike::Reject).rank_order(&first_spikes), [4, 3, 1, 0]);
+}
+
+#[test]
+fn first_spike_decoding_of_a_simulated_network() {
+    // ogni neurone di output è guidato da un input che scatta per la prima volta al passo indicato
+    let trains = vec![vec![0, 0, 0, 0, 0, 1, 1], vec![0, 0, 1, 0, 1, 0, 0], vec![0, 0, 0, 0, 0, 0, 0], vec![0, 0, 0, 1, 1, 1, 1]];
+    let mut nn = NeuralNetwork::new(vec![vec![-60.0; 4]], &[], |_, threshold| boxed(Lif::new(threshold, -65.0, -66.0, 20.0)));
+    let identity: Vec<Vec<f64>> = (0..4).map(|i| (0..4).map(|j| if i == j { 10.0 } else { 0.0 }).collect()).collect();
+    nn.connect_input_layer(InputLayer::from_spikes(trains), &Csr::from_dense(&identity, false), None).unwrap();
+    nn.connect_output(OutputMonitor::new(4));
+    let record = nn.simulate_record().unwrap();
+    assert_eq!(record.first_spikes, [Some(6), Some(3), None, Some(4)]);
+
+    let decoding = Decoding::FirstSpike(FirstSpikeDecoder::default());
+    assert_eq!(decoding.decode(&record), Some(1));
+    assert_eq!(FirstSpikeDecoder::default().rank_order(&record.first_spikes), [1, 3, 0]);
+    // per conteggio vincerebbe il neurone 3
+    assert_eq!(Decoding::default().decode(&record), Some(3));
+}
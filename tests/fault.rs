@@ -0,0 +1,68 @@
+use snn::components::fault::{Fault, FaultComponent, FaultKind};
+use snn::components::hardware::{DatapathPort, DatapathUnit, HardwareLif};
+use snn::components::input_layer::InputLayer;
+use snn::components::models::{boxed, Lif};
+use snn::components::neural_network::NeuralNetwork;
+use snn::components::output::OutputMonitor;
+use snn::components::sparse::Csr;
+
+// un neurone senza decadimento con un input che scatta a ogni passo attraverso una sinapsi di peso 3
+fn network(threshold: f64, steps: usize) -> NeuralNetwork {
+    let mut nn = NeuralNetwork::new(vec![vec![threshold]], &[], |_, threshold| boxed(HardwareLif::new(Lif::new(threshold, -65.0, -66.0, 1e12))));
+    nn.connect_input_layer(InputLayer::from_spikes(vec![vec![1; steps]]), &Csr::from_dense(&[vec![3.0]], true), None).unwrap();
+    nn.connect_output(OutputMonitor::new(1));
+    nn
+}
+
+// ingresso integrato dal neurone sotto soglia a ogni passo
+fn jumps(mut nn: NeuralNetwork, faults: &[Fault]) -> Vec<f64> {
+    faults.iter().for_each(|f| nn.add_fault(*f).unwrap());
+    nn.add_probe(0, 0, 1).unwrap();
+    assert_eq!(nn.simulate().unwrap(), vec![0]);
+    let v_mem = nn.probe(0, 0).unwrap().trace("v_mem").unwrap();
+    std::iter::once(v_mem[0] + 65.0).chain(v_mem.windows(2).map(|w| w[1] - w[0])).map(|x| (x * 1e9).round() / 1e9).collect()
+}
+
+#[test]
+fn stuck_at_bits_corrupt_a_weight() {
+    // 3.0 = 1.5 * 2^1: il bit 52 è il meno significativo dell'esponente (0), il bit 51 il più significativo della mantissa (1)
+    assert_eq!(FaultKind::StuckAt1.apply(3.0, 52), 6.0);
+    assert_eq!(FaultKind::StuckAt0.apply(3.0, 51), 2.0);
+    assert_eq!(FaultKind::StuckAt1.apply(3.0, 51), 3.0);
+    assert_eq!(FaultKind::StuckAt1.apply(3.0, 63), -3.0);
+
+    let weight = |bit, kind| Fault::new(FaultComponent::Weight, 0, 0, Some(0), bit, kind, 0);
+    assert_eq!(jumps(network(1000.0, 5), &[]), [3.0; 5]);
+    assert_eq!(jumps(network(1000.0, 5), &[weight(52, FaultKind::StuckAt1)]), [6.0; 5]);
+    assert_eq!(jumps(network(1000.0, 5), &[weight(51, FaultKind::StuckAt0)]), [2.0; 5]);
+    // un guasto che forza il valore già presente nel bit non ha effetto
+    assert_eq!(jumps(network(1000.0, 5), &[weight(52, FaultKind::StuckAt0)]), [3.0; 5]);
+}
+
+#[test]
+fn stuck_at_bits_corrupt_the_threshold() {
+    // -60 = -1.875 * 2^5: esponente 1028, bit 52 a 0 e bit 54 a 1
+    let threshold = |bit, kind| Fault::new(FaultComponent::VThreshold, 0, 0, None, bit, kind, 0);
+    let run = |faults: &[Fault]| {
+        let mut nn = network(-60.0, 10);
+        faults.iter().for_each(|f| nn.add_fault(*f).unwrap());
+        (nn.simulate().unwrap()[0], nn.thresholds()[0][0])
+    };
+    assert_eq!(run(&[]), (3, -60.0));
+    // soglia a -120, sempre superata
+    assert_eq!(run(&[threshold(52, FaultKind::StuckAt1)]), (10, -120.0));
+    // soglia a -3.75, irraggiungibile in 10 passi
+    assert_eq!(run(&[threshold(54, FaultKind::StuckAt0)]), (0, -3.75));
+}
+
+#[test]
+fn bit_flip_on_a_signal_lasts_only_for_its_step() {
+    let flip = |component, time| Fault::new(component, 0, 0, None, 52, FaultKind::BitFlip, time);
+    let input = FaultComponent::Datapath { unit: DatapathUnit::WeightAdder, port: DatapathPort::Input1 };
+    assert_eq!(jumps(network(1000.0, 8), &[flip(input, 4)]), [3.0, 3.0, 3.0, 6.0, 3.0, 3.0, 3.0, 3.0]);
+    // lo stesso bit-flip sul peso memorizzato resta fino alla fine della simulazione
+    let weight = Fault { synapse: Some(0), ..flip(FaultComponent::Weight, 4) };
+    assert_eq!(jumps(network(1000.0, 8), &[weight]), [3.0, 3.0, 3.0, 6.0, 6.0, 6.0, 6.0, 6.0]);
+    // un bit-flip fuori dalla simulazione non ha effetto
+    assert_eq!(jumps(network(1000.0, 8), &[flip(input, 9)]), [3.0; 8]);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use serde_json::json;
+use snn::components::fixed::{Overflow, QFormat, Rounding};
+use snn::components::neural_network::NeuralNetwork;
+use snn::components::rng::Rng;
+
+// formato con 3 bit di parte intera e 4 di parte frazionaria: valori in [-8, 7.9375] a passi di 1/16
+const Q: QFormat = QFormat { integer_bits: 3, fraction_bits: 4 };
+
+fn quantize(x: f64, overflow: Overflow, rounding: Rounding) -> f64 {
+    Q.quantize(x, overflow, rounding, &mut Rng::new(0))
+}
+
+#[test]
+fn saturation_clamps_to_the_format_range() {
+    let saturate = |x| quantize(x, Overflow::Saturate, Rounding::Nearest);
+    assert_eq!(saturate(7.9375), 7.9375);
+    assert_eq!(saturate(10.0), 7.9375);
+    assert_eq!(saturate(-8.0), -8.0);
+    assert_eq!(saturate(-10.0), -8.0);
+    assert_eq!(saturate(f64::INFINITY), 7.9375);
+    assert_eq!(saturate(f64::NAN), 0.0);
+    assert_eq!(Q.overflow(1000, Overflow::Saturate), 127);
+}
+
+#[test]
+fn wrap_drops_the_bits_beyond_the_format() {
+    let wrap = |x| quantize(x, Overflow::Wrap, Rounding::Nearest);
+    // 10 * 16 = 160, che su 8 bit in complemento a due vale 160 - 256
+    assert_eq!(wrap(10.0), -6.0);
+    assert_eq!(wrap(-10.0), 6.0);
+    assert_eq!(wrap(8.0), -8.0);
+    assert_eq!(wrap(7.9375), 7.9375);
+    assert_eq!(Q.overflow(256 + 5, Overflow::Wrap), 5);
+    // i valori senza bit significativi nella finestra del formato vengono saturati
+    assert_eq!(wrap(f64::NEG_INFINITY), -8.0);
+    assert_eq!(wrap(1e30), 7.9375);
+}
+
+#[test]
+fn truncate_and_nearest_rounding() {
+    let truncate = |x| quantize(x, Overflow::Saturate, Rounding::Truncate);
+    let nearest = |x| quantize(x, Overflow::Saturate, Rounding::Nearest);
+    // 0.3 * 16 = 4.8
+    assert_eq!(truncate(0.3), 0.25);
+    assert_eq!(truncate(-0.3), -0.3125);
+    assert_eq!(nearest(0.3), 0.3125);
+    assert_eq!(nearest(-0.3), -0.3125);
+    // i casi a metà (4.5 / 16) vanno verso +inf
+    assert_eq!(nearest(0.28125), 0.3125);
+    assert_eq!(nearest(-0.28125), -0.25);
+    assert_eq!(truncate(0.5), 0.5);
+    assert_eq!(nearest(0.5), 0.5);
+}
+
+#[test]
+fn stochastic_rounding_is_unbiased() {
+    let mut rng = Rng::new(3);
+    let samples: Vec<f64> = (0..20_000).map(|_| Q.quantize(0.3, Overflow::Saturate, Rounding::Stochastic, &mut rng)).collect();
+    assert!(samples.iter().all(|q| *q == 0.25 || *q == 0.3125));
+    // arrotonda verso l'alto con probabilità 0.8
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    assert!((mean - 0.3).abs() < 1e-3, "{mean}");
+    assert_eq!(Q.quantize(0.5, Overflow::Saturate, Rounding::Stochastic, &mut rng), 0.5);
+    // con lo stesso seme la sequenza si ripete
+    let again: Vec<f64> = {
+        let mut rng = Rng::new(3);
+        (0..100).map(|_| Q.quantize(0.3, Overflow::Saturate, Rounding::Stochastic, &mut rng)).collect()
+    };
+    assert_eq!(again, samples[..100]);
+}
+
+#[test]
+fn loading_reports_quantization_errors() {
+    let path = common::write_network(
+        "fixed_quantization",
+        json!({
+            "tau": 20, "rest_potential": -65, "reset_potential": -66, "thresholds": [[-60.0, -60.0]],
+            "input_weights": [[[0.3, 10.0], [1.5, -0.03]]],
+            "intra_layer_weights": [{ "rows": 2, "cols": 2, "triplets": [[0, 1, -0.5], [1, 0, -0.75]] }],
+            "fixed_point": {
+                "membrane": { "integer_bits": 16, "fraction_bits": 16 },
+                "weight": { "integer_bits": 3, "fraction_bits": 4 },
+                "decay": { "integer_bits": 1, "fraction_bits": 15 },
+            },
+        }),
+        &["1010", "0110"],
+    );
+    let nn = NeuralNetwork::from_json(&path).unwrap();
+    let errors = nn.quantization_errors();
+    assert_eq!(errors.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), ["intra_layer_weights[0]", "input_weights[0]"]);
+
+    // i pesi intra-layer sono rappresentabili
+    assert_eq!((errors[0].count, errors[0].max_abs, errors[0].rmse, errors[0].overflowed), (2, 0.0, 0.0, 0));
+
+    // 0.3 -> 0.3125, 10 -> 7.9375 (saturato), 1.5 esatto, -0.03 -> 0
+    let input = &errors[1];
+    let abs = [0.0125, 2.0625, 0.0, 0.03];
+    assert_eq!((input.count, input.overflowed, input.zeroed), (4, 1, 1));
+    assert!((input.max_abs - 2.0625).abs() < 1e-12);
+    assert!((input.mean_abs - abs.iter().sum::<f64>() / 4.0).abs() < 1e-12);
+    assert!((input.rmse - (abs.iter().map(|e| e * e).sum::<f64>() / 4.0).sqrt()).abs() < 1e-12);
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use snn::components::engine::Engine;
+use snn::components::hardware::HardwareLif;
+use snn::components::models::{boxed, Lif, NeuronModel};
+use snn::components::rng::Rng;
+
+#[test]
+fn hardware_lif_updates_like_lif() {
+    let lif = Lif::new(-55.0, -65.0, -66.0, 20.0);
+    let hardware = HardwareLif::new(lif);
+    let (mut s_lif, mut s_hw) = (lif.init_state(), hardware.init_state());
+    let mut rng = Rng::new(11);
+    for _ in 0..2000 {
+        // passi senza ingressi, con ingressi eccitatori e inibitori
+        let inputs: Vec<f64> = (0..rng.below(4)).map(|_| 8.0 * rng.next_f64() - 3.0).collect();
+        let (fired_lif, fired_hw) = (lif.update(&mut s_lif, &inputs, 1.0, 0.0), hardware.update(&mut s_hw, &inputs, 1.0, 0.0));
+        assert_eq!(fired_lif, fired_hw);
+        if fired_lif {
+            lif.reset(&mut s_lif);
+            hardware.reset(&mut s_hw);
+        }
+        assert_eq!(lif.v_mem(&s_lif).to_bits(), hardware.v_mem(&s_hw).to_bits());
+    }
+}
+
+#[test]
+fn hardware_lif_networks_match_lif() {
+    let hardware = |threshold| boxed(HardwareLif::new(Lif::new(threshold, -65.0, -66.0, 20.0)));
+    for seed in 0..10 {
+        for engine in [Engine::Threaded, Engine::ClockDriven, Engine::EventDriven, Engine::Parallel] {
+            let mut reference = common::random_network(seed, 6, &[8, 5, 3], 60, common::lif);
+            let mut nn = common::random_network(seed, 6, &[8, 5, 3], 60, hardware);
+            reference.set_engine(engine);
+            nn.set_engine(engine);
+            reference.add_probe(1, 0, 1).unwrap();
+            nn.add_probe(1, 0, 1).unwrap();
+            assert_eq!(reference.simulate_record().unwrap(), nn.simulate_record().unwrap(), "seed {seed}, {engine:?}");
+            assert_eq!(reference.probe(1, 0).unwrap().samples(), nn.probe(1, 0).unwrap().samples(), "seed {seed}, {engine:?}");
+        }
+    }
+}
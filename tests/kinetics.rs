@@ -0,0 +1,55 @@
+use snn::components::kinetics::{Kernel, Kinetics, KineticsConfig, ReversalPotentials};
+use snn::components::synapse::Projection;
+
+fn kinetics(kernel: Kernel, reversal: Option<ReversalPotentials>) -> Kinetics {
+    Kinetics::new(KineticsConfig { layer: 0, projection: Projection::Input, kernel, reversal })
+}
+
+// corrente dei passi successivi a una singola spike al passo 0, con il potenziale postsinaptico fisso
+fn response(kinetics: &mut Kinetics, weight: f64, v_mem: f64, steps: usize) -> Vec<f64> {
+    (0..steps).map(|t| kinetics.current(if t == 0 { weight } else { 0.0 }, weight, v_mem, 1.0)).collect()
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn exponential_current_decays_after_a_spike() {
+    let current = response(&mut kinetics(Kernel::Exponential { tau: 5.0 }, None), 3.0, -65.0, 20);
+    assert!(current.iter().enumerate().all(|(k, i)| close(*i, 3.0 * (-(k as f64) / 5.0).exp())), "{current:?}");
+}
+
+#[test]
+fn alpha_current_peaks_at_the_weight_after_tau() {
+    let current = response(&mut kinetics(Kernel::Alpha { tau: 4.0 }, None), 2.0, -65.0, 30);
+    // i(k) = w * (k / tau) * exp(1 - k / tau)
+    assert!(current.iter().enumerate().all(|(k, i)| close(*i, 2.0 * k as f64 / 4.0 * (1.0 - k as f64 / 4.0).exp())), "{current:?}");
+    assert!(close(current[4], 2.0));
+}
+
+#[test]
+fn dual_exponential_current_rises_then_decays() {
+    let current = response(&mut kinetics(Kernel::DualExponential { tau_rise: 2.0, tau_decay: 8.0 }, None), 1.0, -65.0, 60);
+    let peak = current.iter().cloned().fold(0.0, f64::max);
+    let t_peak = current.iter().position(|i| *i == peak).unwrap();
+    // il picco continuo è pari al peso, quello campionato sui passi interi non lo supera
+    assert!(peak <= 1.0 && peak > 0.95, "{current:?}");
+    assert!(current[..=t_peak].windows(2).all(|w| w[1] > w[0]), "{current:?}");
+    assert!(current[t_peak..].windows(2).all(|w| w[1] < w[0]), "{current:?}");
+    // lontano dal picco domina il decadimento lento
+    assert!((current[50] / current[49] - (-1.0f64 / 8.0).exp()).abs() < 1e-6);
+}
+
+#[test]
+fn conductance_current_is_driven_by_the_reversal_potential() {
+    let reversal = Some(ReversalPotentials { e_exc: 0.0, e_inh: -80.0 });
+    let exc = response(&mut kinetics(Kernel::Exponential { tau: 5.0 }, reversal), 0.5, -60.0, 10);
+    assert!(exc.iter().enumerate().all(|(k, i)| close(*i, 0.5 * (-(k as f64) / 5.0).exp() * 60.0)), "{exc:?}");
+    // la conduttanza dipende dal modulo del peso, il segno dal potenziale di inversione inibitorio
+    let inh = response(&mut kinetics(Kernel::Exponential { tau: 5.0 }, reversal), -0.5, -60.0, 10);
+    assert!(inh.iter().zip(&exc).all(|(i, e)| close(*i, -e / 3.0)), "{inh:?}");
+    // al potenziale di inversione la corrente si annulla
+    let at_reversal = response(&mut kinetics(Kernel::Exponential { tau: 5.0 }, reversal), 0.5, 0.0, 10);
+    assert!(at_reversal.iter().all(|i| *i == 0.0));
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use serde_json::json;
+use snn::components::errors::SNNError;
+use snn::components::models::{AdEx, Izhikevich, IzhikevichPreset, NeuronModel};
+use snn::components::neural_network::NeuralNetwork;
+
+fn network(name: &str, model: serde_json::Value) -> String {
+    // un neurone guidato da una corrente costante
+    common::write_network(
+        name,
+        json!({
+            "tau": 20, "rest_potential": -65, "reset_potential": -66, "thresholds": [[-55.0]],
+            "input_weights": [[[10.0]]], "intra_layer_weights": [[[0.0]]],
+            "model": model,
+        }),
+        &[&"1".repeat(200)],
+    )
+}
+
+fn trace(name: &str, model: serde_json::Value) -> (Vec<i32>, Vec<f64>) {
+    let mut nn = NeuralNetwork::from_json(&network(name, model)).unwrap();
+    nn.add_probe(0, 0, 1).unwrap();
+    let counts = nn.simulate().unwrap();
+    (counts, nn.probe(0, 0).unwrap().trace("u").unwrap())
+}
+
+#[test]
+fn izhikevich_presets_follow_the_published_table() {
+    let parameters = |preset| {
+        let m = Izhikevich::from_preset(preset);
+        (m.a, m.b, m.c, m.d)
+    };
+    assert_eq!(parameters(IzhikevichPreset::RegularSpiking), (0.02, 0.2, -65.0, 8.0));
+    assert_eq!(parameters(IzhikevichPreset::FastSpiking), (0.1, 0.2, -65.0, 2.0));
+    assert_eq!(parameters(IzhikevichPreset::IntrinsicallyBursting), (0.02, 0.2, -55.0, 4.0));
+    assert_eq!(parameters(IzhikevichPreset::Chattering), (0.02, 0.2, -50.0, 2.0));
+    assert_eq!(parameters(IzhikevichPreset::LowThreshold), (0.02, 0.25, -65.0, 2.0));
+}
+
+#[test]
+fn izhikevich_preset_in_json_selects_its_parameters() {
+    let presets = [
+        ("regular_spiking", (0.02, 0.2, -65.0, 8.0)),
+        ("fast_spiking", (0.1, 0.2, -65.0, 2.0)),
+        ("intrinsically_bursting", (0.02, 0.2, -55.0, 4.0)),
+        ("chattering", (0.02, 0.2, -50.0, 2.0)),
+        ("low_threshold", (0.02, 0.25, -65.0, 2.0)),
+    ];
+    let mut traces = vec![];
+    for (preset, (a, b, c, d)) in presets {
+        // il preset e i parametri espliciti producono la stessa dinamica
+        let named = trace(&format!("izhikevich_{preset}"), json!({ "type": "izhikevich", "preset": preset }));
+        let explicit = trace(&format!("izhikevich_{preset}_explicit"), json!({ "type": "izhikevich", "a": a, "b": b, "c": c, "d": d }));
+        assert!(named.0[0] > 0, "{preset}");
+        assert_eq!(named, explicit, "{preset}");
+        traces.push(named);
+    }
+    // senza preset si usa il regular spiking, e preset diversi danno dinamiche diverse
+    assert_eq!(trace("izhikevich_default", json!({ "type": "izhikevich" })), traces[0]);
+    assert!(traces.iter().skip(1).all(|t| *t != traces[0]));
+}
+
+#[test]
+fn izhikevich_rejects_unknown_presets() {
+    let path = network("izhikevich_unknown", json!({ "type": "izhikevich", "preset": "thalamo_cortical" }));
+    match NeuralNetwork::from_json(&path) {
+        Err(SNNError::BadFormatError(message)) => assert!(message.contains("thalamo_cortical"), "{message}"),
+        res => panic!("expected a format error, got {:?}", res.map(|nn| nn.shape())),
+    }
+}
+
+#[test]
+fn adex_resets_to_v_reset_and_accumulates_adaptation() {
+    let model = AdEx::new(-70.6, -50.4, -58.0);
+    let mut state = model.init_state();
+    let mut spikes = vec![];
+    let mut w_after = vec![];
+    for t in 0..2000 {
+        if model.update(&mut state, &[1000.0], 0.1, 0.0) {
+            assert_eq!(state.v, model.v_peak);
+            let w = state.w;
+            model.reset(&mut state);
+            assert_eq!(state.v, model.v_reset);
+            assert_eq!(state.w, w + model.b);
+            spikes.push(t);
+            w_after.push(state.w);
+        }
+    }
+    assert!(spikes.len() >= 4, "{spikes:?}");
+    // la corrente di adattamento cresce di spike in spike e allunga gli intervalli
+    assert!(w_after.windows(2).all(|w| w[1] > w[0]), "{w_after:?}");
+    let isi: Vec<usize> = spikes.windows(2).map(|s| s[1] - s[0]).collect();
+    assert!(isi.last() > isi.first(), "{isi:?}");
+}
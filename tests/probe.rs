@@ -0,0 +1,62 @@
+mod common;
+
+use snn::components::engine::Engine;
+
+#[test]
+fn probe_records_every_n_steps() {
+    let mut nn = common::random_network(1, 5, &[6, 3], 40, common::lif);
+    nn.add_probe(0, 2, 4).unwrap();
+    nn.simulate().unwrap();
+    let probe = nn.probe(0, 2).unwrap();
+    assert_eq!(probe.names(), ["v_mem", "elapsed"]);
+    let steps: Vec<usize> = probe.samples().iter().map(|(ts, _)| *ts).collect();
+    assert_eq!(steps, (1..=10).map(|k| 4 * k).collect::<Vec<_>>());
+    assert!(nn.probe(0, 1).is_none());
+}
+
+#[test]
+fn probe_is_cleared_between_simulations() {
+    let mut nn = common::random_network(2, 5, &[4], 30, common::lif);
+    nn.add_probe(0, 0, 1).unwrap();
+    nn.simulate().unwrap();
+    let first = nn.probe(0, 0).unwrap().samples().to_vec();
+    nn.reset_state();
+    nn.simulate().unwrap();
+    assert_eq!(first, nn.probe(0, 0).unwrap().samples());
+    assert_eq!(first.len(), 30);
+}
+
+#[test]
+fn probe_traces_match_across_engines() {
+    let mut traces = vec![];
+    for engine in [Engine::Threaded, Engine::ClockDriven, Engine::EventDriven, Engine::Parallel] {
+        let mut nn = common::random_network(3, 6, &[8, 4], 50, common::lif);
+        nn.set_engine(engine);
+        nn.add_probe(1, 3, 1).unwrap();
+        nn.simulate().unwrap();
+        traces.push(nn.probe(1, 3).unwrap().trace("v_mem").unwrap());
+    }
+    assert!(traces.iter().all(|t| *t == traces[0]), "{traces:?}");
+}
+
+#[test]
+fn probe_rejects_missing_neurons() {
+    let mut nn = common::random_network(4, 3, &[4, 2], 10, common::lif);
+    assert!(nn.add_probe(2, 0, 1).is_err());
+    assert!(nn.add_probe(1, 2, 1).is_err());
+}
+
+#[test]
+fn probe_exports_csv() {
+    let mut nn = common::random_network(5, 3, &[3], 10, common::lif);
+    nn.add_probe(0, 0, 5).unwrap();
+    nn.simulate().unwrap();
+    let path = std::env::temp_dir().join("snn_probe_test.csv");
+    nn.probe(0, 0).unwrap().write_csv(path.to_str().unwrap()).unwrap();
+    let csv = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "ts,v_mem,elapsed");
+    assert!(lines[1].starts_with("5,") && lines[2].starts_with("10,"));
+}
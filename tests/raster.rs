@@ -0,0 +1,62 @@
+mod common;
+
+use snn::components::raster::{RasterTarget, SpikeRaster};
+
+fn path(name: &str) -> String {
+    std::env::temp_dir().join(name).to_str().unwrap().to_string()
+}
+
+#[test]
+fn raster_round_trips_through_csv_and_binary() {
+    let mut nn = common::random_network(11, 5, &[6, 3], 40, common::lif);
+    let id = nn.add_raster(RasterTarget::Layer(0), None).unwrap();
+    // il raster limitato conserva solo gli ultimi 10 eventi
+    let limited = nn.add_raster(RasterTarget::Layer(0), Some(10)).unwrap();
+    nn.simulate().unwrap();
+    let raster = nn.raster(id).unwrap();
+    let events: Vec<(usize, usize)> = raster.events().collect();
+    assert!(events.len() > 10, "{events:?}");
+    assert!(events.windows(2).all(|w| w[0] < w[1]), "{events:?}");
+
+    let csv_path = path("snn_raster_round_trip.csv");
+    raster.write_csv(&csv_path).unwrap();
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    std::fs::remove_file(&csv_path).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("ts,neuron"));
+    let parsed: Vec<(usize, usize)> = lines
+        .map(|line| {
+            let (ts, neuron) = line.split_once(',').unwrap();
+            (ts.parse().unwrap(), neuron.parse().unwrap())
+        })
+        .collect();
+    assert_eq!(parsed, events);
+
+    for (id, name) in [(id, "snn_raster_round_trip.bin"), (limited, "snn_raster_limited.bin")] {
+        let raster = nn.raster(id).unwrap();
+        let bin_path = path(name);
+        raster.write_binary(&bin_path).unwrap();
+        let read = SpikeRaster::read_binary(&bin_path, RasterTarget::Layer(0));
+        std::fs::remove_file(&bin_path).unwrap();
+        let read = read.unwrap();
+        assert_eq!(read.events().collect::<Vec<_>>(), raster.events().collect::<Vec<_>>());
+        assert_eq!(read.dropped(), raster.dropped());
+    }
+    let limited = nn.raster(limited).unwrap();
+    assert_eq!(limited.events().collect::<Vec<_>>(), events[events.len() - 10..]);
+    assert_eq!(limited.dropped(), events.len() - 10);
+}
+
+#[test]
+fn binary_raster_rejects_truncated_files() {
+    let mut raster = SpikeRaster::new(RasterTarget::Input, None);
+    raster.record(1, 0);
+    raster.record(3, 2);
+    let bin_path = path("snn_raster_truncated.bin");
+    raster.write_binary(&bin_path).unwrap();
+    let bytes = std::fs::read(&bin_path).unwrap();
+    std::fs::write(&bin_path, &bytes[..bytes.len() - 4]).unwrap();
+    let read = SpikeRaster::read_binary(&bin_path, RasterTarget::Input);
+    std::fs::remove_file(&bin_path).unwrap();
+    assert!(read.is_err());
+}
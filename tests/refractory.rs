@@ -0,0 +1,38 @@
+use snn::components::input_layer::InputLayer;
+use snn::components::models::{boxed, AdEx, Izhikevich, NeuronDynamics};
+use snn::components::neural_network::NeuralNetwork;
+use snn::components::output::OutputMonitor;
+use snn::components::refractory::RefractoryConfig;
+use snn::components::sparse::Csr;
+
+// un solo neurone con periodo refrattario assoluto, stimolato solo al primo passo da un ingresso con peso weight
+fn driven_neuron<F: Fn() -> Box<dyn NeuronDynamics>>(model: F, weight: f64, steps: usize) -> NeuralNetwork {
+    let refractory = RefractoryConfig { absolute: 5, relative: None };
+    let mut nn = NeuralNetwork::new(vec![vec![0.0]], &[refractory], |_, _| model());
+    nn.connect(0, 0, &Csr::from_dense(&[vec![0.0]], false), None).unwrap();
+    nn.connect_input_layer(InputLayer::from_spikes(vec![(0..steps).map(|ts| i8::from(ts == 0)).collect()]), &Csr::from_dense(&[vec![weight]], true), None).unwrap();
+    nn.connect_output(OutputMonitor::new(1));
+    nn.add_probe(0, 0, 1).unwrap();
+    nn
+}
+
+#[test]
+fn izhikevich_resets_during_absolute_refractory() {
+    // con il reset sopra la soglia il neurone torna al picco da solo, anche senza ingressi durante il periodo refrattario
+    let mut nn = driven_neuron(|| boxed(Izhikevich::new(0.02, 0.2, -40.0, 0.0)), 200.0, 30);
+    let counts = nn.simulate().unwrap();
+    assert!(counts[0] > 1);
+    // ogni superamento del picco riporta il potenziale a c, anche quando la spike non viene emessa
+    let v = nn.probe(0, 0).unwrap().trace("v_mem").unwrap();
+    assert!(v.iter().all(|v| *v < 30.0), "{v:?}");
+}
+
+#[test]
+fn adex_resets_during_absolute_refractory() {
+    let mut nn = driven_neuron(|| boxed(AdEx::new(-70.0, -50.0, -42.0)), 20000.0, 30);
+    let counts = nn.simulate().unwrap();
+    assert!(counts[0] > 1);
+    // il picco è v_t + 5 delta_t = -40
+    let v = nn.probe(0, 0).unwrap().trace("v_mem").unwrap();
+    assert!(v.iter().all(|v| *v < -40.0), "{v:?}");
+}
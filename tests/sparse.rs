@@ -0,0 +1,37 @@
+use snn::components::sparse::Csr;
+
+fn write_words(name: &str, words: &[u64]) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(name);
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+#[test]
+fn binary_round_trip() {
+    let matrix = Csr::from_dense(&[vec![0.0, 1.5], vec![-2.0, 0.0]], false);
+    let path = std::env::temp_dir().join("snn_sparse_round_trip.bin");
+    matrix.write_binary(path.to_str().unwrap()).unwrap();
+    let read = Csr::<f64>::from_binary(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read.unwrap().iter().collect::<Vec<_>>(), vec![(0, 1, 1.5), (1, 0, -2.0)]);
+}
+
+#[test]
+fn binary_rejects_corrupt_headers() {
+    let one = 1.0f64.to_bits();
+    let cases: [(&str, Vec<u64>); 3] = [
+        // numero di triplette enorme in un file di una sola tripletta
+        ("snn_sparse_huge.bin", vec![2, 2, u64::MAX / 8, 0, 0, one]),
+        // file troncato
+        ("snn_sparse_truncated.bin", vec![2, 2, 2, 0, 0, one]),
+        // più triplette di quante ne contenga la matrice
+        ("snn_sparse_overfull.bin", vec![1, 1, 2, 0, 0, one, 0, 0, one]),
+    ];
+    for (name, words) in cases {
+        let path = write_words(name, &words);
+        let read = Csr::<f64>::from_binary(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(read.is_err(), "{name}");
+    }
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use serde_json::json;
+use snn::components::neural_network::NeuralNetwork;
+use snn::components::stdp::{Stdp, StdpConfig, StdpRule, WeightBounds};
+use snn::components::synapse::Projection;
+
+fn config(rule: StdpRule, bounds: WeightBounds) -> StdpConfig {
+    StdpConfig {
+        layer: 0,
+        projection: Projection::Input,
+        enabled: true,
+        rule,
+        learning_rate: 1.0,
+        a_plus: 0.1,
+        a_minus: 0.12,
+        tau_plus: 10.0,
+        tau_minus: 20.0,
+        a3_plus: 0.05,
+        a3_minus: 0.0,
+        tau_x: 100.0,
+        tau_y: 100.0,
+        w_min: 0.0,
+        w_max: 1.0,
+        bounds,
+    }
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-12
+}
+
+#[test]
+fn pre_before_post_potentiates() {
+    let mut stdp = Stdp::new(config(StdpRule::Pair, WeightBounds::Hard));
+    let w = stdp.on_pre(0.5);
+    assert_eq!(w, 0.5);
+    stdp.step(1.0);
+    let w = stdp.on_post(w);
+    assert!(close(w, 0.5 + 0.1 * (-0.1f64).exp()), "{w}");
+}
+
+#[test]
+fn post_before_pre_depresses() {
+    let mut stdp = Stdp::new(config(StdpRule::Pair, WeightBounds::Hard));
+    let w = stdp.on_post(0.5);
+    assert_eq!(w, 0.5);
+    stdp.step(1.0);
+    let w = stdp.on_pre(w);
+    assert!(close(w, 0.5 - 0.12 * (-0.05f64).exp()), "{w}");
+}
+
+#[test]
+fn hard_bounds_clamp_and_soft_bounds_scale() {
+    let mut stdp = Stdp::new(config(StdpRule::Pair, WeightBounds::Hard));
+    stdp.on_pre(0.98);
+    assert_eq!(stdp.on_post(0.98), 1.0);
+    stdp.on_post(0.01);
+    stdp.on_post(0.01);
+    assert_eq!(stdp.on_pre(0.01), 0.0);
+
+    // con i limiti moltiplicativi il potenziamento è scalato di (w_max - w)
+    let mut stdp = Stdp::new(config(StdpRule::Pair, WeightBounds::Soft));
+    stdp.on_pre(0.8);
+    assert!(close(stdp.on_post(0.8), 0.8 + 0.1 * 0.2));
+}
+
+#[test]
+fn triplet_rule_adds_post_post_potentiation() {
+    // coppia pre-post preceduta da una spike post: la regola a triplette potenzia di più
+    let potentiation = |rule| {
+        let mut stdp = Stdp::new(config(rule, WeightBounds::Hard));
+        stdp.on_post(0.5);
+        stdp.step(1.0);
+        stdp.on_pre(0.5);
+        stdp.step(1.0);
+        stdp.on_post(0.5) - 0.5
+    };
+    let pair = potentiation(StdpRule::Pair);
+    let triplet = potentiation(StdpRule::Triplet);
+    assert!(close(pair, 0.1 * (-0.1f64).exp()));
+    assert!(close(triplet, pair + 0.05 * (-0.02f64).exp() * (-0.1f64).exp()), "{triplet}");
+}
+
+#[test]
+fn learned_weights_survive_export() {
+    let template = common::write_network(
+        "stdp_export",
+        json!({
+            "tau": 20, "rest_potential": -65, "reset_potential": -66, "thresholds": [[-60.0, -58.0]],
+            "input_weights": [[[6.0, 3.0, 4.0], [2.0, 7.0, 4.0]]], "intra_layer_weights": [[[0.0, -1.0], [-1.0, 0.0]]],
+            "stdp": [{ "layer": 0, "projection": "input", "a_plus": 0.5, "a_minus": 0.4, "tau_plus": 10, "tau_minus": 10,
+                       "w_min": 0, "w_max": 10 }],
+        }),
+        &["1010110100101", "0110011011010", "1101100110111"],
+    );
+    let mut nn = NeuralNetwork::from_json(&template).unwrap();
+    nn.simulate().unwrap();
+    let learned = template.replace("network.json", "learned.json");
+    nn.save_json(&template, &learned).unwrap();
+
+    // la rete ricaricata esporta gli stessi pesi appresi, diversi da quelli iniziali
+    let frozen = NeuralNetwork::from_json(&learned).unwrap();
+    let again = template.replace("network.json", "again.json");
+    frozen.save_json(&learned, &again).unwrap();
+    let weights = |path: &str| serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(path).unwrap()).unwrap()["input_weights"].clone();
+    assert_eq!(weights(&again), weights(&learned));
+    assert_ne!(weights(&learned), weights(&template));
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use serde_json::json;
+use snn::components::neural_network::NeuralNetwork;
+use snn::components::stp::{Stp, StpConfig};
+use snn::components::synapse::Projection;
+
+fn stp(u: f64, tau_rec: f64, tau_facil: f64) -> Stp {
+    Stp::new(StpConfig { layer: 0, projection: Projection::Input, u, tau_rec, tau_facil })
+}
+
+// frazione del peso trasmessa da una spike a ogni passo di train, con un passo di recupero prima di ciascuno come nella sinapsi
+fn efficacies(stp: &mut Stp, train: &str) -> Vec<f64> {
+    train
+        .chars()
+        .filter_map(|c| {
+            stp.step(1.0);
+            (c == '1').then(|| stp.on_pre())
+        })
+        .collect()
+}
+
+#[test]
+fn repeated_spikes_depress_and_resources_recover() {
+    let mut synapse = stp(0.5, 20.0, 0.0);
+    let burst = efficacies(&mut synapse, "1111");
+    assert_eq!(burst[0], 0.5);
+    // dopo la prima spike restano metà delle risorse, che recuperano per un passo
+    assert!((burst[1] - 0.5 * (1.0 - 0.5 * (-1.0f64 / 20.0).exp())).abs() < 1e-12, "{burst:?}");
+    assert!(burst.windows(2).all(|w| w[1] < w[0]), "{burst:?}");
+
+    // una pausa lunga rispetto a tau_rec riporta l'efficacia quasi a U
+    let after = efficacies(&mut synapse, &format!("{}1", "0".repeat(200)));
+    assert!(after[0] > burst[0] - 1e-4 && after[0] <= burst[0], "{after:?}");
+}
+
+#[test]
+fn repeated_spikes_facilitate_and_utilization_decays() {
+    let mut synapse = stp(0.1, 1.0, 50.0);
+    let burst = efficacies(&mut synapse, "11111");
+    // alla prima spike l'utilizzo sale da U a U + U(1 - U)
+    assert!((burst[0] - 0.19).abs() < 1e-12, "{burst:?}");
+    assert!(burst.windows(2).all(|w| w[1] > w[0]), "{burst:?}");
+
+    let after = efficacies(&mut synapse, &format!("{}1", "0".repeat(1000)));
+    assert!((after[0] - burst[0]).abs() < 1e-6, "{after:?}");
+}
+
+#[test]
+fn network_transmits_the_depressed_weight() {
+    // neurone senza decadimento e sotto soglia: ogni salto del potenziale è il peso trasmesso
+    let train = format!("1111{}1", "0".repeat(100));
+    let path = common::write_network(
+        "stp_depression",
+        json!({
+            "tau": 1e12, "rest_potential": -65, "reset_potential": -66, "thresholds": [[1000.0]],
+            "input_weights": [[[10.0]]], "intra_layer_weights": [[[0.0]]],
+            "stp": [{ "layer": 0, "projection": "input", "u": 0.5, "tau_rec": 20.0 }],
+        }),
+        &[&train],
+    );
+    let mut nn = NeuralNetwork::from_json(&path).unwrap();
+    nn.add_probe(0, 0, 1).unwrap();
+    nn.simulate().unwrap();
+    let v_mem = nn.probe(0, 0).unwrap().trace("v_mem").unwrap();
+    let mut jumps = vec![v_mem[0] + 65.0];
+    jumps.extend(v_mem.windows(2).map(|w| w[1] - w[0]).filter(|d| d.abs() > 1e-9));
+
+    let expected: Vec<f64> = efficacies(&mut stp(0.5, 20.0, 0.0), &train).iter().map(|e| 10.0 * e).collect();
+    assert_eq!(jumps.len(), expected.len(), "{jumps:?}");
+    assert!(jumps.iter().zip(&expected).all(|(a, b)| (a - b).abs() < 1e-6), "{jumps:?} {expected:?}");
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use snn::components::engine::Engine;
+use snn::components::errors::SNNError;
+use snn::components::input_layer::InputLayer;
+use snn::components::models::{boxed, Lif};
+use snn::components::neural_network::NeuralNetwork;
+use snn::components::output::OutputMonitor;
+use snn::components::sparse::Csr;
+
+#[test]
+fn threaded_engine_reports_neuron_panics() {
+    let mut nn = NeuralNetwork::new(vec![vec![0.0]], &[], |_, _| boxed(common::Explode));
+    nn.connect_input_layer(InputLayer::from_spikes(vec![vec![1; 10]]), &Csr::from_dense(&[vec![1.0]], true), None).unwrap();
+    nn.connect_output(OutputMonitor::new(1));
+
+    match nn.simulate() {
+        Err(SNNError::EngineError(message)) => assert!(message.contains("boom"), "{message}"),
+        res => panic!("expected an engine error, got {res:?}"),
+    }
+    // il neurone torna nel suo layer anche dopo il panic
+    assert_eq!(nn.shape(), vec![vec![1]]);
+}
+
+#[test]
+fn threaded_engine_tolerates_inputs_without_fanout() {
+    // l'input 1 non raggiunge nessun neurone
+    let record = |engine: Engine| {
+        let mut nn = NeuralNetwork::new(vec![vec![-60.0, -62.0]], &[], |_, threshold| boxed(Lif::new(threshold, -65.0, -66.0, 20.0)));
+        let weights = Csr::from_triplets(2, 3, vec![(0, 0, 3.0), (1, 2, 4.0)]).unwrap();
+        let trains = vec![vec![1, 1, 0, 1, 1, 1, 0, 1]; 3];
+        nn.connect_input_layer(InputLayer::from_spikes(trains), &weights, None).unwrap();
+        nn.connect_output(OutputMonitor::new(2));
+        nn.set_engine(engine);
+        nn.simulate_record().unwrap()
+    };
+    let reference = record(Engine::ClockDriven);
+    assert!(reference.counts.iter().all(|c| *c > 0), "{reference:?}");
+    assert_eq!(record(Engine::Threaded), reference);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use serde_json::json;
+use snn::components::neural_network::NeuralNetwork;
+
+fn network(name: &str, model: serde_json::Value, train: &str) -> String {
+    // un neurone che scatta alla prima spike di input, con soglia adattiva
+    common::write_network(
+        name,
+        json!({
+            "tau": 20, "rest_potential": -65, "reset_potential": -66, "thresholds": [[-60.0]],
+            "input_weights": [[[100.0]]], "intra_layer_weights": [[[0.0]]],
+            "adaptive_threshold": [{ "increment": 2.0, "tau": 10.0 }],
+            "model": model,
+        }),
+        &[train],
+    )
+}
+
+fn theta(nn: &NeuralNetwork) -> f64 {
+    nn.thresholds()[0][0] + 60.0
+}
+
+#[test]
+fn theta_rises_after_a_spike_and_decays() {
+    let mut nn = NeuralNetwork::from_json(&network("theta_spike", json!({ "type": "lif" }), "1")).unwrap();
+    nn.simulate().unwrap();
+    assert!((theta(&nn) - 2.0).abs() < 1e-12, "{}", theta(&nn));
+
+    // dopo la spike theta decade per i 9 passi successivi
+    let mut nn = NeuralNetwork::from_json(&network("theta_decay", json!({ "type": "lif" }), "1000000000")).unwrap();
+    assert_eq!(nn.simulate().unwrap(), vec![1]);
+    assert!((theta(&nn) - 2.0 * (-9.0f64 / 10.0).exp()).abs() < 1e-12, "{}", theta(&nn));
+}
+
+#[test]
+fn adapted_threshold_survives_export() {
+    let template = network("theta_export", json!({ "type": "lif" }), "1000000000");
+    let mut nn = NeuralNetwork::from_json(&template).unwrap();
+    nn.simulate().unwrap();
+    assert!(theta(&nn) > 0.0);
+    let saved = template.replace("network.json", "saved.json");
+    nn.save_json(&template, &saved).unwrap();
+
+    // la rete congelata parte dalla soglia adattata, senza più adattamento
+    let frozen = NeuralNetwork::from_json(&saved).unwrap();
+    assert_eq!(frozen.thresholds(), nn.thresholds());
+    assert!(!std::fs::read_to_string(&saved).unwrap().contains("adaptive_threshold"));
+}
+
+#[test]
+fn export_rejects_adaptation_of_models_without_thresholds() {
+    // l'izhikevich usa v_peak come soglia e ignorerebbe il campo thresholds esportato
+    let template = network("theta_izhikevich", json!({ "type": "izhikevich" }), "1000000000");
+    let mut nn = NeuralNetwork::from_json(&template).unwrap();
+    nn.simulate().unwrap();
+    assert!(nn.save_json(&template, &template.replace("network.json", "saved.json")).is_err());
+}